@@ -0,0 +1,100 @@
+// The quote packs and the word list, read from disk at startup and read again whenever
+// one of the files changes, so content can be updated without rebuilding anything.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use thockflow::quotes::{self, Pack, PACK_SOURCES};
+use thockflow::words::{WordBank, WORDS_PATH};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct Corpus {
+    root: PathBuf,
+    packs: RwLock<Arc<Vec<Pack>>>,
+    words: RwLock<Arc<WordBank>>,
+}
+
+impl Corpus {
+    pub fn load(root: &Path) -> Result<Self> {
+        Ok(Corpus {
+            root: root.to_path_buf(),
+            packs: RwLock::new(Arc::new(read_packs(root)?)),
+            words: RwLock::new(Arc::new(read_words(root)?)),
+        })
+    }
+
+    pub fn packs(&self) -> Arc<Vec<Pack>> {
+        self.packs.read().unwrap().clone()
+    }
+
+    pub fn words(&self) -> Arc<WordBank> {
+        self.words.read().unwrap().clone()
+    }
+
+    // Polls the files for changes. A file that fails to parse is reported and the
+    // previous corpus kept, so a bad edit doesn't take the site down.
+    pub fn watch(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut last_modified = self.modified();
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let modified = self.modified();
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+                let reloaded = read_packs(&self.root).and_then(|packs| read_words(&self.root).map(|words| (packs, words)));
+                match reloaded {
+                    Ok((packs, words)) => {
+                        *self.packs.write().unwrap() = Arc::new(packs);
+                        *self.words.write().unwrap() = Arc::new(words);
+                        eprintln!("reloaded quotes and words");
+                    }
+                    Err(e) => eprintln!("keeping previous quotes and words: {e:?}"),
+                }
+            }
+        });
+    }
+
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        PACK_SOURCES
+            .iter()
+            .map(|source| source.path)
+            .chain([WORDS_PATH])
+            .map(|path| fs::metadata(self.root.join(path)).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+fn read_packs(root: &Path) -> Result<Vec<Pack>> {
+    PACK_SOURCES
+        .iter()
+        .map(|source| {
+            let raw = source.read(root).map_err(|e| anyhow!("{}: {}", source.path, e))?;
+            let pack = Pack::parse(source, &raw).map_err(|e| anyhow!("{}: {}", source.path, e))?;
+            if pack.quotes.is_empty() {
+                return Err(anyhow!("{}: no quotes", source.path));
+            }
+            Ok(pack)
+        })
+        .collect()
+}
+
+fn read_words(root: &Path) -> Result<WordBank> {
+    let words = WordBank::read(root).map_err(|e| anyhow!("{}: {}", WORDS_PATH, e))?;
+    if words.is_empty() {
+        return Err(anyhow!("{}: no words", WORDS_PATH));
+    }
+    Ok(words)
+}
+
+// Unknown or missing pack ids fall back to the default pack.
+pub fn pack<'a>(packs: &'a [Pack], id: Option<&str>) -> &'a Pack {
+    let id = quotes::pack_source_or_default(id).id;
+    packs.iter().find(|p| p.id == id).expect("every pack source is loaded")
+}
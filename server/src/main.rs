@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
 
 use anyhow::Result;
 use axum::body::{Body, BoxBody};
@@ -8,17 +10,21 @@ use axum::extract::Query;
 use axum::http::{header, HeaderMap, HeaderValue, Request, Response, StatusCode};
 use axum::response::{Html, IntoResponse};
 use axum::routing::{get_service, MethodRouter};
-use axum::Extension;
+use axum::{Extension, Json};
 use axum::{routing::get, Router};
 use futures::future::BoxFuture;
 use futures::ready;
-use thockflow::ServerAppProps;
+use thockflow::{quotes, selection, words, Route, ServerAppProps};
 use once_cell::sync::Lazy;
 use tokio_util::task::LocalPoolHandle;
 use tower::Service;
 use tower_http::services::ServeDir;
 use yew_router::Routable;
 
+mod corpus;
+
+use corpus::Corpus;
+
 lazy_static::lazy_static!(
     // Use the source HTML as a template
     static ref INDEX_HTML: String = {
@@ -35,45 +41,123 @@ lazy_static::lazy_static!(
 
 static LOCAL_POOL: Lazy<LocalPoolHandle> = Lazy::new(|| LocalPoolHandle::new(num_cpus::get()));
 
-fn html_wasm_init_head(init_quote_index: usize) -> String {
+// JSON-encode a string for inlining into a <script>. `<`, `>` and `&` are escaped so
+// neither `</script>` nor `<!--` in the text can change how the browser parses the tag.
+fn js_string_literal(s: &str) -> String {
+    serde_json::to_string(s)
+        .unwrap()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+}
+
+// Uniform in [0, 1), seeded from the clock
+fn random() -> f64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    words::Rng::new(now as u64).next_f64()
+}
+
+fn today() -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    selection::utc_day(now as f64)
+}
+
+fn js_number_or_null(n: Option<u64>) -> String {
+    n.map(|n| n.to_string()).unwrap_or_else(|| "null".to_string())
+}
+
+struct InitHead<'a> {
+    pack_id: &'a str,
+    quote: &'a quotes::Quote,
+    missing_quote_id: Option<&'a str>,
+    quote_linked: bool,
+    seed: Option<u64>,
+    day: Option<u64>,
+}
+
+fn html_wasm_init_head(init: &InitHead) -> String {
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
     format!(
         r#"
-    <script>window.THOCKFLOW_INIT_INDEX = {};</script>
+    <script>window.THOCKFLOW_PACK = {}; window.THOCKFLOW_QUOTE = {}; window.THOCKFLOW_MISSING_ID = {}; window.THOCKFLOW_LINKED = {}; window.THOCKFLOW_SEED = {}; window.THOCKFLOW_DAY = {};</script>
     <script type="module">
       import init from "{js_path}?v={ts}";
       init({{ module_or_path: "{wasm_path}?v={ts}" }});
     </script>
 "#,
-        init_quote_index,
+        js_string_literal(init.pack_id),
+        js_string_literal(&serde_json::to_string(init.quote).unwrap()),
+        init.missing_quote_id.map(js_string_literal).unwrap_or_else(|| "null".to_string()),
+        init.quote_linked,
+        js_number_or_null(init.seed),
+        js_number_or_null(init.day),
         js_path = *APP_JS_PATH,
         wasm_path = *APP_WASM_PATH,
         ts = timestamp,
     )
 }
 
+// A quote from the pack by id, or a generated one recreated from its id
+fn find_quote(pack: &quotes::Pack, bank: &words::WordBank, id: &str) -> Option<quotes::Quote> {
+    quotes::find_by_id(&pack.quotes, id).cloned().or_else(|| words::generated_quote(id, pack, bank))
+}
+
 async fn index(
     Extension(index_html_s): Extension<String>,
+    Extension(corpus): Extension<Arc<Corpus>>,
     url: Request<Body>,
     Query(queries): Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    let init_quote_index = {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        (now % 10000) as usize // Simple pseudo-random using nanoseconds
+    let packs = corpus.packs();
+    let words = corpus.words();
+    let pack = corpus::pack(&packs, queries.get("pack").map(String::as_str));
+    // Seeds are 32 bits, see `selection::next_seed`
+    let seed = queries.get("seed").and_then(|s| s.parse::<u32>().ok()).map(u64::from);
+    let day = (Route::recognize(url.uri().path()) == Some(Route::Daily)).then(today);
+    // Honour ?quote=<id> if it still exists, otherwise pick one and let the page say so
+    let requested_id = queries.get("quote").cloned().filter(|_| day.is_none());
+    let (init_quote, missing_quote_id) = match requested_id.as_deref().and_then(|id| find_quote(pack, &words, id)) {
+        Some(quote) => (quote, None),
+        None => {
+            let quote = match (day, seed) {
+                (Some(day), _) => selection::quote_of_the_day(&pack.quotes, day),
+                (None, Some(seed)) => quotes::QuoteRequest::seeded(pack.id, seed).choose(&pack.quotes, selection::seeded_random(seed)),
+                (None, None) => quotes::QuoteRequest::default().choose(&pack.quotes, random()),
+            };
+            (quote.cloned().expect("packs are never empty"), requested_id)
+        }
     };
+    let init_pack = pack.id.to_string();
+    let quote_linked = requested_id.is_some() && missing_quote_id.is_none();
+    let head = html_wasm_init_head(&InitHead {
+        pack_id: &init_pack,
+        quote: &init_quote,
+        missing_quote_id: missing_quote_id.as_deref(),
+        quote_linked,
+        seed,
+        day,
+    });
 
     let out = LOCAL_POOL
         .spawn_pinned(move || async move {
             let props = ServerAppProps {
                 path: url.uri().path().to_owned().into(),
                 queries,
-                init_quote_index: Some(init_quote_index),
+                init_pack: Some(init_pack),
+                init_quote: Some(init_quote),
+                missing_quote_id,
+                quote_linked,
+                seed,
+                day,
             };
             let mut out = String::new();
             yew::ServerRenderer::<thockflow::ServerApp>::with_props(move || props)
@@ -86,13 +170,47 @@ async fn index(
     // Remove dev script tag if present to avoid duplicate loads
     let html = index_html_s
         .replace("<body>", &format!("<body>{}", out))
-        .replace("</head>", &format!("{}</head>", html_wasm_init_head(init_quote_index)));
+        .replace("</head>", &format!("{}</head>", head));
     (
         HeaderMap::from_iter([(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))]),
         Html(html),
     )
 }
 
+// See `quotes::QuoteRequest`
+async fn api_quote(
+    Extension(corpus): Extension<Arc<Corpus>>,
+    Query(request): Query<quotes::QuoteRequest>,
+) -> Response<BoxBody> {
+    let packs = corpus.packs();
+    let words = corpus.words();
+    let pack = corpus::pack(&packs, request.pack.as_deref());
+    let quote = match &request.id {
+        Some(id) => find_quote(pack, &words, id),
+        None => {
+            let random = request.seed.map(selection::seeded_random).unwrap_or_else(random);
+            request.choose(&pack.quotes, random).cloned()
+        }
+    };
+    match quote {
+        Some(quote) => (
+            HeaderMap::from_iter([(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))]),
+            Json(quote),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn api_tags(Extension(corpus): Extension<Arc<Corpus>>) -> impl IntoResponse {
+    let packs = corpus.packs();
+    let all: Vec<quotes::Quote> = packs.iter().flat_map(|p| p.quotes.iter().cloned()).collect();
+    (
+        HeaderMap::from_iter([(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))]),
+        Json(quotes::all_tags(&all)),
+    )
+}
+
 async fn handle_error(e: impl std::fmt::Debug) -> impl IntoResponse {
     eprintln!("{e:?}");
     StatusCode::BAD_REQUEST
@@ -100,6 +218,9 @@ async fn handle_error(e: impl std::fmt::Debug) -> impl IntoResponse {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let corpus = Arc::new(Corpus::load(Path::new("."))?);
+    corpus.clone().watch();
+
     let mut app_wasm_serve = ServeDir::new("app_wasm");
     if option_env!("AXUM_PRECOMPRESSED_WASM").is_some() {
         app_wasm_serve = app_wasm_serve.precompressed_br();
@@ -111,12 +232,16 @@ async fn main() -> Result<()> {
         get(index),
         route(*APP_JS_PATH, app_wasm_serve.clone())
             .route(*APP_WASM_PATH, app_wasm_serve)
+            .route(quotes::QUOTE_ENDPOINT, get(api_quote))
+            .route(quotes::TAGS_ENDPOINT, get(api_tags))
             // Serve built assets from Vite dist first
             .route("/assets/*path", dist_serve)
             // Fallback to legacy static dir
             .fallback(static_serve),
     );
-    let route_service = get_service(route_service).layer(Extension(INDEX_HTML.to_string()));
+    let route_service = get_service(route_service)
+        .layer(Extension(INDEX_HTML.to_string()))
+        .layer(Extension(corpus));
 
     if lambda_web::is_running_on_lambda() {
         eprintln!("starting server on lambda");
@@ -1,10 +1,41 @@
 fn main() {
     #[cfg(target_arch = "wasm32")]
     wasm_logger::init(wasm_logger::Config::new(log::Level::Trace));
-    let init_quote_index = web_sys::window()
-        .and_then(|w| w.get("THOCKFLOW_INIT_INDEX"))
-        .and_then(|v| v.as_f64())
-        .map(|v| v as usize);
-        
-    yew::Renderer::<thockflow::App>::with_props(thockflow::AppProps { init_quote_index }).hydrate();
+    let window_string = |name: &str| {
+        web_sys::window()
+            .and_then(|w| w.get(name))
+            .and_then(|v| v.as_string())
+    };
+    let init_pack = window_string("THOCKFLOW_PACK");
+    // Inlined by the server as a JSON string, so the first quote needs no extra request
+    let init_quote = window_string("THOCKFLOW_QUOTE").and_then(|json| match serde_json::from_str(&json) {
+        Ok(quote) => Some(quote),
+        Err(e) => {
+            log::warn!("failed to read the inlined quote: {}", e);
+            None
+        }
+    });
+    let missing_quote_id = window_string("THOCKFLOW_MISSING_ID");
+    let window_u64 = |name: &str| {
+        web_sys::window()
+            .and_then(|w| w.get(name))
+            .and_then(|v| v.as_f64())
+            .map(|n| n as u64)
+    };
+    let seed = window_u64("THOCKFLOW_SEED");
+    let day = window_u64("THOCKFLOW_DAY");
+    let quote_linked = web_sys::window()
+        .and_then(|w| w.get("THOCKFLOW_LINKED"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    yew::Renderer::<thockflow::App>::with_props(thockflow::AppProps {
+        init_pack,
+        init_quote,
+        missing_quote_id,
+        quote_linked,
+        seed,
+        day,
+    })
+    .hydrate();
 }
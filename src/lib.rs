@@ -1,5 +1,7 @@
 mod typing;
 
+pub use typing::{drill, quotes, selection, words};
+
 use std::collections::HashMap;
 
 use yew::prelude::*;
@@ -10,7 +12,16 @@ use yew_router::{
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct QuoteContext {
-    pub index: usize,
+    pub pack: String,
+    pub quote: quotes::Quote,
+    // The id that was asked for, when it no longer exists and `quote` is a replacement
+    pub missing_id: Option<String>,
+    // Whether `quote` came from the URL rather than being picked at random
+    pub linked: bool,
+    // From `?seed=`, continues the seeded sequence the quote was picked from
+    pub seed: Option<u64>,
+    // Set on the quote of the day page, days since the Unix epoch in UTC
+    pub day: Option<u64>,
 }
 
 #[derive(Routable, PartialEq, Clone)]
@@ -19,22 +30,40 @@ pub enum Route {
     Home,
     #[at("/typing")]
     Typing,
+    #[at("/custom")]
+    Custom,
+    #[at("/daily")]
+    Daily,
 }
 
 #[derive(Properties, PartialEq, Debug, Default)]
 pub struct AppProps {
-    pub init_quote_index: Option<usize>,
+    pub init_pack: Option<String>,
+    pub init_quote: Option<quotes::Quote>,
+    pub missing_quote_id: Option<String>,
+    pub quote_linked: bool,
+    pub seed: Option<u64>,
+    pub day: Option<u64>,
 }
 
 #[function_component]
 pub fn App(props: &AppProps) -> Html {
-    let context = props.init_quote_index.map(|i| QuoteContext { index: i });
-    
+    let context = props.init_quote.clone().map(|quote| QuoteContext {
+        pack: props.init_pack.clone().unwrap_or_else(|| quotes::DEFAULT_PACK.to_string()),
+        quote,
+        missing_id: props.missing_quote_id.clone(),
+        linked: props.quote_linked,
+        seed: props.seed,
+        day: props.day,
+    });
+
     html! {
         <ContextProvider<Option<QuoteContext>> context={context}>
-            <BrowserRouter>
-                <Switch<Route> render={switch} />
-            </BrowserRouter>
+            <typing::SettingsProvider>
+                <BrowserRouter>
+                    <Switch<Route> render={switch} />
+                </BrowserRouter>
+            </typing::SettingsProvider>
         </ContextProvider<Option<QuoteContext>>>
     }
 }
@@ -43,7 +72,12 @@ pub fn App(props: &AppProps) -> Html {
 pub struct ServerAppProps {
     pub path: String,
     pub queries: HashMap<String, String>,
-    pub init_quote_index: Option<usize>,
+    pub init_pack: Option<String>,
+    pub init_quote: Option<quotes::Quote>,
+    pub missing_quote_id: Option<String>,
+    pub quote_linked: bool,
+    pub seed: Option<u64>,
+    pub day: Option<u64>,
 }
 
 #[function_component]
@@ -53,13 +87,22 @@ pub fn ServerApp(props: &ServerAppProps) -> Html {
         .push_with_query(&*props.path, &props.queries)
         .unwrap();
         
-    let context = props.init_quote_index.map(|i| QuoteContext { index: i });
+    let context = props.init_quote.clone().map(|quote| QuoteContext {
+        pack: props.init_pack.clone().unwrap_or_else(|| quotes::DEFAULT_PACK.to_string()),
+        quote,
+        missing_id: props.missing_quote_id.clone(),
+        linked: props.quote_linked,
+        seed: props.seed,
+        day: props.day,
+    });
 
     html! {
         <ContextProvider<Option<QuoteContext>> context={context}>
-            <Router history={history}>
-                <Switch<Route> render={switch} />
-            </Router>
+            <typing::SettingsProvider>
+                <Router history={history}>
+                    <Switch<Route> render={switch} />
+                </Router>
+            </typing::SettingsProvider>
         </ContextProvider<Option<QuoteContext>>>
     }
 }
@@ -77,6 +120,16 @@ fn Navbar() -> Html {
                         {"Typing"}
                     </button>
                 </Link<Route>>
+                <Link<Route> classes="p-4 text-3xl" to={Route::Daily}>
+                    <button>
+                        {"Daily"}
+                    </button>
+                </Link<Route>>
+                <Link<Route> classes="p-4 text-3xl" to={Route::Custom}>
+                    <button>
+                        {"Custom text"}
+                    </button>
+                </Link<Route>>
                 <a class="p-4 text-3xl" href="https://github.com/drpngx/thockflow">{"GitHub"}</a>
             </div>
         </div>
@@ -120,7 +173,22 @@ fn switch(route: Route) -> Html {
                     Route::Typing => html! {
                         <div class="w-full font-body flex px-2 flex-col items-center place-content-around">
                             <div class="flex flex-col">
-                                <typing::TypingHome />
+                                // Keyed by route so nothing carries over to or from the daily page
+                                <typing::TypingHome key="typing" />
+                            </div>
+                        </div>
+                    },
+                    Route::Daily => html! {
+                        <div class="w-full font-body flex px-2 flex-col items-center place-content-around">
+                            <div class="flex flex-col">
+                                <typing::TypingHome key="daily" source={typing::TextSource::Daily} />
+                            </div>
+                        </div>
+                    },
+                    Route::Custom => html! {
+                        <div class="w-full font-body flex px-2 flex-col items-center place-content-around">
+                            <div class="flex flex-col w-full">
+                                <typing::CustomText />
                             </div>
                         </div>
                     },
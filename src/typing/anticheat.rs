@@ -0,0 +1,195 @@
+// Detection of synthetic input, paste and automation.
+// A run that trips any rule is still shown to the user, but is marked as unverified
+// so it can be kept off leaderboards.
+
+// No human sustains keystrokes this close together.
+pub const MIN_HUMAN_INTERVAL_MS: f64 = 10.0;
+
+// Number of consecutive sub-`MIN_HUMAN_INTERVAL_MS` intervals that counts as automation.
+// A couple of near-simultaneous keys happen naturally when rolling over keys.
+const MAX_FAST_RUN: usize = 3;
+
+// Scripts replaying text tend to use a fixed delay. Humans never get this even.
+const ROBOTIC_MAX_STDDEV_MS: f64 = 1.0;
+const ROBOTIC_MIN_INTERVALS: usize = 20;
+
+// Raw signals gathered by the hook while the session is running.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputSignals {
+    pub untrusted_events: usize,
+    pub paste_attempts: usize,
+    pub drop_attempts: usize,
+    // Gaps between typed characters. Unlike the keystroke times used for the results,
+    // these are kept when characters are deleted, so backspacing over a scripted burst
+    // doesn't remove it from the evidence. Pauses are taken out, as for the results.
+    pub intervals: Vec<f64>,
+    pub last_keystroke: Option<f64>,
+}
+
+impl InputSignals {
+    pub fn record_keystroke(&mut self, at: f64) {
+        if let Some(last) = self.last_keystroke.replace(at) {
+            self.intervals.push(at - last);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flag {
+    UntrustedEvents,
+    Paste,
+    DragDrop,
+    ImpossiblyFast,
+    RoboticTiming,
+}
+
+impl Flag {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Flag::UntrustedEvents => "synthetic key events",
+            Flag::Paste => "paste",
+            Flag::DragDrop => "drag and drop",
+            Flag::ImpossiblyFast => "impossibly fast keystrokes",
+            Flag::RoboticTiming => "constant keystroke timing",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Verdict {
+    pub flags: Vec<Flag>,
+}
+
+impl Verdict {
+    pub fn is_verified(&self) -> bool {
+        self.flags.is_empty()
+    }
+}
+
+pub fn assess(signals: &InputSignals) -> Verdict {
+    let mut flags = Vec::new();
+
+    if signals.untrusted_events > 0 {
+        flags.push(Flag::UntrustedEvents);
+    }
+    if signals.paste_attempts > 0 {
+        flags.push(Flag::Paste);
+    }
+    if signals.drop_attempts > 0 {
+        flags.push(Flag::DragDrop);
+    }
+
+    if has_fast_run(&signals.intervals) {
+        flags.push(Flag::ImpossiblyFast);
+    }
+    if is_robotic(&signals.intervals) {
+        flags.push(Flag::RoboticTiming);
+    }
+
+    Verdict { flags }
+}
+
+fn has_fast_run(intervals: &[f64]) -> bool {
+    let mut run = 0;
+    for &interval in intervals {
+        if interval < MIN_HUMAN_INTERVAL_MS {
+            run += 1;
+            if run > MAX_FAST_RUN {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    false
+}
+
+fn is_robotic(intervals: &[f64]) -> bool {
+    if intervals.len() < ROBOTIC_MIN_INTERVALS {
+        return false;
+    }
+    let n = intervals.len() as f64;
+    let mean = intervals.iter().sum::<f64>() / n;
+    let variance = intervals.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    variance.sqrt() < ROBOTIC_MAX_STDDEV_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic jitter so "human" timings aren't perfectly even.
+    fn human_times(n: usize) -> Vec<f64> {
+        let mut t = 0.0;
+        (0..n)
+            .map(|i| {
+                t += 120.0 + ((i * 37) % 90) as f64;
+                t
+            })
+            .collect()
+    }
+
+    fn recorded(times: &[f64]) -> InputSignals {
+        let mut signals = InputSignals::default();
+        for &t in times {
+            signals.record_keystroke(t);
+        }
+        signals
+    }
+
+    #[test]
+    fn test_human_typing_is_verified() {
+        let verdict = assess(&recorded(&human_times(100)));
+        assert!(verdict.is_verified(), "{:?}", verdict);
+    }
+
+    #[test]
+    fn test_signals_are_flagged() {
+        let signals = InputSignals {
+            untrusted_events: 1,
+            paste_attempts: 2,
+            drop_attempts: 1,
+            ..recorded(&human_times(10))
+        };
+        let verdict = assess(&signals);
+        assert_eq!(verdict.flags, vec![Flag::UntrustedEvents, Flag::Paste, Flag::DragDrop]);
+    }
+
+    #[test]
+    fn test_burst_of_instant_keys_is_flagged() {
+        let mut times = human_times(20);
+        let last = *times.last().unwrap();
+        times.extend((1..=10).map(|i| last + i as f64 * 2.0));
+        let verdict = assess(&recorded(&times));
+        assert!(verdict.flags.contains(&Flag::ImpossiblyFast));
+    }
+
+    #[test]
+    fn test_deleted_burst_is_still_flagged() {
+        // A scripted burst, deleted again and followed by normal typing
+        let mut signals = recorded(&human_times(20));
+        let last = signals.last_keystroke.unwrap();
+        for i in 1..=10 {
+            signals.record_keystroke(last + i as f64 * 2.0);
+        }
+        let after = signals.last_keystroke.unwrap();
+        for t in human_times(20) {
+            signals.record_keystroke(after + t);
+        }
+        assert!(assess(&signals).flags.contains(&Flag::ImpossiblyFast));
+    }
+
+    #[test]
+    fn test_key_rollover_is_not_flagged() {
+        // Two keys landing together once in a while is normal.
+        let times = vec![0.0, 150.0, 155.0, 300.0, 305.0, 450.0];
+        assert!(assess(&recorded(&times)).is_verified());
+    }
+
+    #[test]
+    fn test_constant_interval_is_flagged() {
+        let times: Vec<f64> = (0..50).map(|i| i as f64 * 80.0).collect();
+        let verdict = assess(&recorded(&times));
+        assert_eq!(verdict.flags, vec![Flag::RoboticTiming]);
+    }
+}
@@ -0,0 +1,129 @@
+// How the caret looks and moves.
+//
+// The caret is an overlay positioned over the character it is on, so the text never
+// reflows when it moves. Its outer element is placed by the cursor effect in `TypingHome`
+// and the inner one draws the shape.
+
+use serde::{Deserialize, Serialize};
+
+use super::theme::with_alpha;
+
+pub const MAX_DURATION_MS: u32 = 500;
+
+// Blinking starts after this long without a keystroke
+const BLINK_DELAY_MS: u32 = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaretStyle {
+    #[default]
+    Line,
+    Block,
+    Underline,
+    Outline,
+}
+
+impl CaretStyle {
+    pub const ALL: [CaretStyle; 4] = [CaretStyle::Line, CaretStyle::Block, CaretStyle::Underline, CaretStyle::Outline];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaretStyle::Line => "Line",
+            CaretStyle::Block => "Block",
+            CaretStyle::Underline => "Underline",
+            CaretStyle::Outline => "Outline",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaretOptions {
+    pub style: CaretStyle,
+    // Slide between characters rather than jump
+    pub smooth: bool,
+    pub duration_ms: u32,
+    // Blink while no keys are being pressed
+    pub blink: bool,
+}
+
+impl Default for CaretOptions {
+    fn default() -> Self {
+        CaretOptions { style: CaretStyle::Line, smooth: true, duration_ms: 100, blink: false }
+    }
+}
+
+impl CaretOptions {
+    // Inline style for the outer element, over a character at (left, top) of the given size
+    pub fn position_style(&self, left: f64, top: f64, width: f64, height: f64) -> String {
+        let transition = if self.smooth && self.duration_ms > 0 {
+            let ms = self.duration_ms.min(MAX_DURATION_MS);
+            format!("left {ms}ms ease-out, top {ms}ms ease-out, width {ms}ms ease-out")
+        } else {
+            "none".to_string()
+        };
+        format!(
+            "left: {}px; top: {}px; width: {}px; height: {}px; opacity: 1; transition: {};",
+            left, top, width, height, transition
+        )
+    }
+
+    // Inline style for the inner element that draws the caret
+    pub fn shape_style(&self, colour: &str) -> String {
+        let shape = match self.style {
+            CaretStyle::Line => format!("left: 0; top: 0; bottom: 0; width: 2px; background-color: {};", colour),
+            CaretStyle::Block => format!("inset: 0; background-color: {};", with_alpha(colour, 0.4)),
+            CaretStyle::Underline => format!("left: 0; right: 0; bottom: 0.1em; height: 3px; background-color: {};", colour),
+            CaretStyle::Outline => format!("inset: 0; border: 1px solid {};", colour),
+        };
+        if self.blink {
+            format!("{} animation-delay: {}ms;", shape, BLINK_DELAY_MS)
+        } else {
+            shape
+        }
+    }
+
+    // Tailwind animation for the inner element, see tailwind.config.js
+    pub fn shape_class(&self) -> &'static str {
+        if self.blink {
+            "absolute animate-blink"
+        } else {
+            "absolute"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_style() {
+        let smooth = CaretOptions::default();
+        assert!(smooth.position_style(1.0, 2.0, 3.0, 4.0).contains("left: 1px; top: 2px; width: 3px; height: 4px;"));
+        assert!(smooth.position_style(0.0, 0.0, 0.0, 0.0).contains("left 100ms ease-out"));
+
+        let slow = CaretOptions { duration_ms: 10_000, ..smooth };
+        assert!(slow.position_style(0.0, 0.0, 0.0, 0.0).contains("left 500ms"));
+
+        let instant = CaretOptions { smooth: false, ..smooth };
+        assert!(instant.position_style(0.0, 0.0, 0.0, 0.0).ends_with("transition: none;"));
+        let zero = CaretOptions { duration_ms: 0, ..smooth };
+        assert!(zero.position_style(0.0, 0.0, 0.0, 0.0).ends_with("transition: none;"));
+    }
+
+    #[test]
+    fn test_shape_style() {
+        let line = CaretOptions::default();
+        assert!(line.shape_style("#facc15").contains("width: 2px; background-color: #facc15;"));
+        assert_eq!(line.shape_class(), "absolute");
+
+        let block = CaretOptions { style: CaretStyle::Block, blink: true, ..line };
+        assert!(block.shape_style("#facc15").contains("background-color: #facc1566;"));
+        assert!(block.shape_style("#facc15").contains("animation-delay"));
+        assert_eq!(block.shape_class(), "absolute animate-blink");
+
+        let outline = CaretOptions { style: CaretStyle::Outline, ..line };
+        assert!(outline.shape_style("#facc15").contains("border: 1px solid #facc15;"));
+    }
+}
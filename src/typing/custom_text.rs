@@ -0,0 +1,194 @@
+// Custom text mode: practise on text pasted by the user.
+// The text is normalised, split into sections of roughly equal length at sentence
+// boundaries, and each section is played like a quote.
+
+use std::rc::Rc;
+
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use super::hook::TextSource;
+use super::quotes::Quote;
+use super::sections;
+use super::TypingHome;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionSize {
+    Short,
+    Medium,
+    Long,
+}
+
+impl SectionSize {
+    pub const ALL: [SectionSize; 3] = [SectionSize::Short, SectionSize::Medium, SectionSize::Long];
+
+    // Target number of characters per section
+    pub fn chars(&self) -> usize {
+        match self {
+            SectionSize::Short => 150,
+            SectionSize::Medium => 300,
+            SectionSize::Long => 600,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SectionSize::Short => "Short",
+            SectionSize::Medium => "Medium",
+            SectionSize::Long => "Long",
+        }
+    }
+}
+
+// Typographic punctuation with a plain ASCII equivalent that's on every keyboard.
+fn fold_char(c: char) -> Option<&'static str> {
+    match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => Some("'"),
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => Some("\""),
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2212}' => Some("-"),
+        '\u{2026}' => Some("..."),
+        _ => None,
+    }
+}
+
+// Collapses all runs of whitespace (including newlines and non-breaking spaces) to a
+// single space and drops invisible characters. With `fold_smart_quotes`, curly quotes,
+// dashes and ellipses are replaced by their ASCII forms.
+pub fn normalise(raw: &str, fold_smart_quotes: bool) -> String {
+    let mut text = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match fold_char(c) {
+            Some(folded) if fold_smart_quotes => text.push_str(folded),
+            _ if c.is_whitespace() => text.push(' '),
+            // Zero-width spaces, soft hyphens and other format characters can't be typed
+            _ if c.is_control() || matches!(c, '\u{00AD}' | '\u{200B}'..='\u{200D}' | '\u{FEFF}') => {}
+            _ => text.push(c),
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn sections_from_text(raw: &str, fold_smart_quotes: bool, size: SectionSize) -> Vec<Quote> {
+    let text = normalise(raw, fold_smart_quotes);
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let mut whole = Quote::from_text(None, &text, "");
+    whole.source = Some("Custom text".to_string());
+    sections::split(&whole, size.chars())
+}
+
+#[function_component]
+pub fn CustomText() -> Html {
+    let raw = use_state(String::new);
+    let fold_smart_quotes = use_state(|| true);
+    let size = use_state(|| SectionSize::Medium);
+    // Bumped on every start, so the typing view is rebuilt for the new text
+    let session = use_state(|| 0usize);
+    let sections = use_state(|| None::<Rc<Vec<Quote>>>);
+
+    let on_input = {
+        let raw = raw.clone();
+        Callback::from(move |e: InputEvent| raw.set(e.target_unchecked_into::<HtmlTextAreaElement>().value()))
+    };
+
+    let on_fold_change = {
+        let fold_smart_quotes = fold_smart_quotes.clone();
+        Callback::from(move |e: Event| fold_smart_quotes.set(e.target_unchecked_into::<HtmlInputElement>().checked()))
+    };
+
+    let on_size_change = {
+        let size = size.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            if let Some(s) = SectionSize::ALL.into_iter().find(|s| s.label() == value) {
+                size.set(s);
+            }
+        })
+    };
+
+    let preview = sections_from_text(&raw, *fold_smart_quotes, *size);
+
+    let on_start = {
+        let sections = sections.clone();
+        let session = session.clone();
+        let preview = preview.clone();
+        Callback::from(move |_: MouseEvent| {
+            if !preview.is_empty() {
+                session.set(*session + 1);
+                sections.set(Some(Rc::new(preview.clone())));
+            }
+        })
+    };
+
+    let on_edit = {
+        let sections = sections.clone();
+        Callback::from(move |_: MouseEvent| sections.set(None))
+    };
+
+    if let Some(sections) = &*sections {
+        return html! {
+            <div class="flex flex-col">
+                <button class="self-end mb-2 px-4 text-sm text-gray-500 hover:underline" onclick={on_edit}>{"Edit text"}</button>
+                <TypingHome key={*session} source={TextSource::Custom(sections.clone())} />
+            </div>
+        };
+    }
+
+    html! {
+        <div class="w-full px-4" style="max-width: 70vw; margin: 0 auto;">
+            <h2 class="text-3xl font-bold mb-4 text-center">{"Custom text"}</h2>
+            <textarea
+                class="w-full h-64 p-4 rounded-lg bg-gray-100 dark:bg-gray-800 font-mono text-sm"
+                placeholder="Paste an email, a document or a code snippet to practise on"
+                value={(*raw).clone()}
+                oninput={on_input} />
+            <div class="mt-4 flex flex-wrap justify-between items-center gap-4 text-sm">
+                <label class="flex items-center gap-2">
+                    <input type="checkbox" checked={*fold_smart_quotes} onchange={on_fold_change} />
+                    <span class="text-gray-600 dark:text-gray-400">{"Replace smart quotes and dashes with plain ones"}</span>
+                </label>
+                <label class="flex items-center gap-2">
+                    <span class="text-gray-600 dark:text-gray-400">{"Section length"}</span>
+                    <select class="px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_size_change}>
+                        { for SectionSize::ALL.iter().map(|s| html! {
+                            <option value={s.label()} selected={*size == *s}>{format!("{} (~{} characters)", s.label(), s.chars())}</option>
+                        }) }
+                    </select>
+                </label>
+                <span class="text-gray-500">{format!("{} sections", preview.len())}</span>
+                <button class="px-4 py-2 rounded bg-blue-500 text-white disabled:opacity-50" disabled={preview.is_empty()} onclick={on_start}>
+                    {"Start"}
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalise_whitespace() {
+        assert_eq!(normalise("  one\ttwo\n\nthree\u{00A0} four  ", false), "one two three four");
+        assert_eq!(normalise("zero\u{200B}width soft\u{00AD}hyphen", false), "zerowidth softhyphen");
+    }
+
+    #[test]
+    fn test_fold_smart_quotes() {
+        let raw = "\u{201C}It\u{2019}s fine\u{201D} \u{2014} she said\u{2026}";
+        assert_eq!(normalise(raw, true), "\"It's fine\" - she said...");
+        assert_eq!(normalise(raw, false), raw);
+    }
+
+    #[test]
+    fn test_sections_from_text() {
+        assert!(sections_from_text(" \n\t ", true, SectionSize::Short).is_empty());
+        let sections = sections_from_text("Hello,\nworld.", true, SectionSize::Short);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].text, "Hello, world.");
+        assert_eq!(sections[0].source.as_deref(), Some("Custom text"));
+        assert_eq!(sections[0].section.as_ref().map(|s| s.label()).as_deref(), Some("Section 1 of 1"));
+    }
+}
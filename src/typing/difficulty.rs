@@ -0,0 +1,172 @@
+// Heuristic difficulty score for a quote.
+// Higher is harder. The absolute value has no unit; it is only used to rank quotes
+// against each other, which is how the easy/medium/hard buckets are assigned.
+
+use serde::{Deserialize, Serialize};
+
+// The most frequent letter pairs in English text. Anything else counts as rare.
+const COMMON_BIGRAMS: [&str; 40] = [
+    "th", "he", "in", "er", "an", "re", "on", "at", "en", "nd", "ti", "es", "or", "te", "of", "ed",
+    "is", "it", "al", "ar", "st", "to", "nt", "ng", "se", "ha", "as", "ou", "io", "le", "ve", "co",
+    "me", "de", "hi", "ri", "ro", "ic", "ne", "ea",
+];
+
+const HOME_ROW: &str = "asdfghjkl;'";
+const UPPER_ROW: &str = "qwertyuiop[]\\";
+const LOWER_ROW: &str = "zxcvbnm,./";
+const NUMBER_ROW: &str = "`1234567890-=";
+const SHIFTED_NUMBER_ROW: &str = "~!@#$%^&*()_+";
+const SHIFTED_SYMBOLS: &str = "{}|:\"<>?";
+
+// Weights of each component in the final score.
+const RARE_BIGRAM_WEIGHT: f64 = 1.0;
+const PUNCTUATION_WEIGHT: f64 = 4.0;
+const DIGIT_WEIGHT: f64 = 6.0;
+const CAPITAL_WEIGHT: f64 = 3.0;
+const WORD_LENGTH_WEIGHT: f64 = 0.15;
+const REACH_WEIGHT: f64 = 0.5;
+
+// Words up to this length don't add to the score.
+const BASELINE_WORD_LENGTH: f64 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+// Distance of a key from the home row, plus one if it needs shift.
+fn reach(c: char) -> f64 {
+    if c == ' ' {
+        return 0.0;
+    }
+    let shift = if c.is_ascii_uppercase() { 1.0 } else { 0.0 };
+    let lower = c.to_ascii_lowercase();
+    let row = if HOME_ROW.contains(lower) {
+        0.0
+    } else if UPPER_ROW.contains(lower) || LOWER_ROW.contains(lower) {
+        1.0
+    } else if NUMBER_ROW.contains(lower) {
+        2.0
+    } else if SHIFTED_NUMBER_ROW.contains(lower) {
+        3.0
+    } else if SHIFTED_SYMBOLS.contains(lower) {
+        2.0
+    } else {
+        // Not on a US keyboard at all
+        4.0
+    };
+    row + shift
+}
+
+pub fn difficulty(text: &str) -> f64 {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if chars.is_empty() {
+        return 0.0;
+    }
+    let n = chars.len() as f64;
+    let density = |pred: fn(&char) -> bool| chars.iter().filter(|c| pred(c)).count() as f64 / n;
+
+    let punctuation = density(|c| c.is_ascii_punctuation());
+    let digits = density(|c| c.is_ascii_digit());
+    let capitals = density(|c| c.is_uppercase());
+
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let avg_word_length = n / words.len() as f64;
+
+    let mut bigrams = 0usize;
+    let mut rare = 0usize;
+    for word in &words {
+        let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).flat_map(char::to_lowercase).collect();
+        for pair in letters.windows(2) {
+            bigrams += 1;
+            let bigram: String = pair.iter().collect();
+            if !COMMON_BIGRAMS.contains(&bigram.as_str()) {
+                rare += 1;
+            }
+        }
+    }
+    let rare_bigrams = if bigrams > 0 { rare as f64 / bigrams as f64 } else { 0.0 };
+
+    let avg_reach = chars.iter().map(|&c| reach(c)).sum::<f64>() / n;
+
+    RARE_BIGRAM_WEIGHT * rare_bigrams
+        + PUNCTUATION_WEIGHT * punctuation
+        + DIGIT_WEIGHT * digits
+        + CAPITAL_WEIGHT * capitals
+        + WORD_LENGTH_WEIGHT * (avg_word_length - BASELINE_WORD_LENGTH).max(0.0)
+        + REACH_WEIGHT * avg_reach
+}
+
+// Splits scores into thirds, so each bucket holds about the same number of quotes
+// whatever the overall difficulty of the corpus.
+pub fn buckets(scores: &[f64]) -> Vec<Difficulty> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[a].total_cmp(&scores[b]));
+    let mut result = vec![Difficulty::Medium; scores.len()];
+    for (rank, &i) in order.iter().enumerate() {
+        result[i] = match rank * 3 / scores.len() {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        };
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_component_adds_difficulty() {
+        let base = difficulty("the rain in the north is here at the station");
+        assert!(difficulty("the rain in the north is here at 10:45 on 2024-03-17") > base);
+        assert!(difficulty("The Rain In The North Is Here At The Station") > base);
+        assert!(difficulty("the rain (in the north) is here; at the station!") > base);
+        assert!(difficulty("the xylophone quizzically jukeboxed") > base);
+        assert!(difficulty("extraordinarily incomprehensible characteristically") > base);
+    }
+
+    #[test]
+    fn test_reach() {
+        assert_eq!(reach('a'), 0.0);
+        assert_eq!(reach('A'), 1.0);
+        assert_eq!(reach('q'), 1.0);
+        assert_eq!(reach('7'), 2.0);
+        assert_eq!(reach('&'), 3.0);
+    }
+
+    #[test]
+    fn test_empty_text() {
+        assert_eq!(difficulty(""), 0.0);
+        assert_eq!(difficulty("   "), 0.0);
+    }
+
+    #[test]
+    fn test_buckets_are_balanced() {
+        let scores: Vec<f64> = (0..9).rev().map(|i| i as f64).collect();
+        let result = buckets(&scores);
+        assert_eq!(result[0], Difficulty::Hard);
+        assert_eq!(result[8], Difficulty::Easy);
+        for d in Difficulty::ALL {
+            assert_eq!(result.iter().filter(|&&r| r == d).count(), 3);
+        }
+        assert_eq!(buckets(&[1.0]), vec![Difficulty::Easy]);
+        assert!(buckets(&[]).is_empty());
+    }
+}
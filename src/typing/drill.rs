@@ -0,0 +1,300 @@
+// Drills aimed at the letters and letter pairs a user finds hardest.
+//
+// A finished test is scored per character and per bigram, by how often it was mistyped
+// and how much slower than usual it was reached. The worst ones become the drill's
+// targets, and the drill is made of real words from the pack's quotes that contain them.
+// Targets no quote uses are covered from the word list instead. Like generated word tests,
+// the targets and seed are the id, so a drill can be linked from the same pack.
+
+use std::collections::HashMap;
+
+use super::matching::{align_incremental, EditOp};
+use super::metrics::MAX_INTERVAL_MS;
+use super::quotes::{Pack, Quote};
+use super::words::{Rng, WordBank, WordList};
+
+const ID_PREFIX: &str = "drill";
+
+pub const MAX_TARGETS: usize = 6;
+const DRILL_WORDS: usize = 40;
+
+// An error counts this much more than being twice as slow as usual.
+const ERROR_WEIGHT: f64 = 3.0;
+// Too few samples make the timing of a bigram meaningless.
+const MIN_BIGRAM_SAMPLES: usize = 2;
+// Favours common words, so the drill doesn't turn into a list of rare ones. Ranks are
+// counted from the most frequent word.
+const COMMON_WORD_BIAS: f64 = 1_000.0;
+
+#[derive(Default)]
+struct Tally {
+    seen: usize,
+    errors: usize,
+    total_interval: f64,
+    timed: usize,
+}
+
+impl Tally {
+    fn score(&self, mean_interval: f64, min_samples: usize) -> f64 {
+        if self.seen == 0 {
+            return 0.0;
+        }
+        let error_rate = self.errors as f64 / self.seen as f64;
+        let slowness = if self.timed >= min_samples && mean_interval > 0.0 {
+            (self.total_interval / self.timed as f64 / mean_interval - 1.0).max(0.0)
+        } else {
+            0.0
+        };
+        ERROR_WEIGHT * error_rate + slowness
+    }
+}
+
+// Lowercase letters and bigrams of them, worst first. Other characters are left out
+// because the drill is made of plain words.
+pub fn weak_spots(quote: &str, input: &str, keystroke_times: &[f64], error_positions: &[usize]) -> Vec<String> {
+    // The character that should have been typed at each input position, None for extras
+    let expected: Vec<Option<char>> = align_incremental(quote, input)
+        .into_iter()
+        .filter(|(_, _, typed)| typed.is_some())
+        .map(|(op, wanted, _)| if op == EditOp::Insert { None } else { wanted })
+        .map(|c| c.map(|c| c.to_ascii_lowercase()).filter(char::is_ascii_lowercase))
+        .collect();
+
+    let mut chars: HashMap<String, Tally> = HashMap::new();
+    let mut bigrams: HashMap<String, Tally> = HashMap::new();
+    let mut total_interval = 0.0;
+    let mut timed = 0usize;
+
+    for (i, c) in expected.iter().enumerate() {
+        let Some(c) = c else { continue };
+        let is_error = error_positions.contains(&i);
+        let interval = match (i.checked_sub(1).and_then(|p| keystroke_times.get(p)), keystroke_times.get(i)) {
+            (Some(prev), Some(t)) if t - prev <= MAX_INTERVAL_MS => Some(t - prev),
+            _ => None,
+        };
+        let previous = i.checked_sub(1).and_then(|p| expected[p]);
+
+        let record = |tally: &mut Tally| {
+            tally.seen += 1;
+            tally.errors += is_error as usize;
+            if let Some(interval) = interval {
+                tally.total_interval += interval;
+                tally.timed += 1;
+            }
+        };
+        record(chars.entry(c.to_string()).or_default());
+        if let Some(previous) = previous {
+            record(bigrams.entry(format!("{}{}", previous, c)).or_default());
+        }
+        if let Some(interval) = interval {
+            total_interval += interval;
+            timed += 1;
+        }
+    }
+
+    let mean_interval = if timed > 0 { total_interval / timed as f64 } else { 0.0 };
+    let mut scored: Vec<(String, f64)> = chars
+        .into_iter()
+        .map(|(c, tally)| (c, tally.score(mean_interval, 1)))
+        .chain(bigrams.into_iter().map(|(b, tally)| (b, tally.score(mean_interval, MIN_BIGRAM_SAMPLES))))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+    // Ties broken alphabetically so the result doesn't depend on hash order
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scored.into_iter().take(MAX_TARGETS).map(|(target, _)| target).collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drill {
+    // Worst first
+    pub targets: Vec<String>,
+    pub seed: u64,
+}
+
+impl Drill {
+    // e.g. "drill-12345-e-th-q"
+    pub fn id(&self) -> String {
+        format!("{}-{}-{}", ID_PREFIX, self.seed, self.targets.join("-"))
+    }
+
+    pub fn from_id(id: &str) -> Option<Drill> {
+        let mut parts = id.split('-');
+        if parts.next()? != ID_PREFIX {
+            return None;
+        }
+        let seed = parts.next()?.parse().ok()?;
+        let targets: Vec<String> = parts.map(str::to_string).collect();
+        let valid = |t: &String| (1..=2).contains(&t.len()) && t.chars().all(|c| c.is_ascii_lowercase());
+        if targets.is_empty() || targets.len() > MAX_TARGETS || !targets.iter().all(valid) {
+            return None;
+        }
+        Some(Drill { targets, seed })
+    }
+
+    pub fn generate(&self, quotes: &[Quote], bank: &WordBank) -> String {
+        let mut rng = Rng::new(self.seed);
+        // Earlier targets are worse, so they weigh more
+        let target_weights: Vec<(&str, f64)> = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(rank, t)| (t.as_str(), (self.targets.len() - rank) as f64))
+            .collect();
+
+        let corpus = corpus_words(quotes);
+        let (mut words, mut weights) = candidates(corpus.iter().map(String::as_str), &target_weights);
+        let missing: Vec<(&str, f64)> =
+            target_weights.iter().copied().filter(|(t, _)| !words.iter().any(|w| w.contains(t))).collect();
+        if !missing.is_empty() {
+            let (extra, extra_weights) = candidates(bank.words(WordList::Top10k).iter().map(String::as_str), &missing);
+            words.extend(extra);
+            weights.extend(extra_weights);
+        }
+        if words.is_empty() {
+            words = bank.words(WordList::Top200).iter().map(String::as_str).collect();
+            weights = vec![1.0; words.len()];
+        }
+
+        let mut out: Vec<&str> = Vec::with_capacity(DRILL_WORDS);
+        while out.len() < DRILL_WORDS {
+            let word = words[rng.pick_weighted(&weights)];
+            if words.len() > 1 && out.last() == Some(&word) {
+                continue;
+            }
+            out.push(word);
+        }
+        out.join(" ")
+    }
+
+    pub fn to_quote(&self, pack: &Pack, bank: &WordBank) -> Quote {
+        let mut quote = Quote::from_text(Some(self.id()), &self.generate(&pack.quotes, bank), pack.language);
+        quote.source = Some(format!("Drill: {}", self.targets.join(", ")));
+        quote
+    }
+}
+
+// Words that contain a target, from a list ranked most frequent first, with their weights
+fn candidates<'a>(ranked: impl Iterator<Item = &'a str>, targets: &[(&str, f64)]) -> (Vec<&'a str>, Vec<f64>) {
+    let mut words = Vec::new();
+    let mut weights = Vec::new();
+    for (rank, word) in ranked.enumerate() {
+        let weight: f64 = targets.iter().filter(|(t, _)| word.contains(t)).map(|(_, w)| w).sum();
+        if weight > 0.0 {
+            words.push(word);
+            weights.push(weight / (1.0 + rank as f64 / COMMON_WORD_BIAS));
+        }
+    }
+    (words, weights)
+}
+
+// Every word of the quotes, lowercased, most frequent first. Words with anything but
+// letters in them, like contractions and numbers, are left out.
+fn corpus_words(quotes: &[Quote]) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in quotes.iter().flat_map(|q| q.text.split_whitespace()) {
+        let word = word.trim_matches(|c: char| !c.is_alphabetic()).to_lowercase();
+        if !word.is_empty() && word.chars().all(char::is_alphabetic) {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    // Ties broken alphabetically so the ranks don't depend on hash order
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.into_iter().map(|(word, _)| word).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    use super::*;
+    use super::super::quotes::{pack_source, parse_corpus, DEFAULT_PACK};
+
+    fn english() -> Pack {
+        let source = pack_source(DEFAULT_PACK).unwrap();
+        let raw = source.read(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        Pack::parse(source, &raw).unwrap()
+    }
+
+    // Keystrokes for typing `text` with a fixed gap, except `slow` chars which take `slow_ms`
+    fn times(text: &str, slow: &str, slow_ms: f64) -> Vec<f64> {
+        let mut t = 0.0;
+        text.chars()
+            .map(|c| {
+                t += if slow.contains(c) { slow_ms } else { 100.0 };
+                t
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_errors_make_a_weak_spot() {
+        let quote = "the quick brown fox";
+        let input = "the quick brown fox";
+        let keystroke_times = times(input, "", 0.0);
+        // Mistyped the 'q' then corrected it
+        let spots = weak_spots(quote, input, &keystroke_times, &[4]);
+        assert_eq!(spots[0], "q");
+        assert!(weak_spots(quote, input, &keystroke_times, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_slow_characters_make_a_weak_spot() {
+        let quote = "zebra lazy dozen";
+        let spots = weak_spots(quote, quote, &times(quote, "z", 400.0), &[]);
+        assert_eq!(spots[0], "z");
+        assert!(spots.iter().all(|s| s.contains('z')));
+    }
+
+    #[test]
+    fn test_non_letters_are_ignored() {
+        let quote = "a, b. c! d? 1 2";
+        let spots = weak_spots(quote, quote, &times(quote, "", 0.0), &[1, 4, 7, 12]);
+        assert!(spots.iter().all(|s| s.chars().all(|c| c.is_ascii_lowercase())));
+        assert!(spots.len() <= MAX_TARGETS);
+    }
+
+    #[test]
+    fn test_drill_words_contain_targets() {
+        let pack = english();
+        // Nothing may come from the word list while the corpus has words for every target
+        let bank = WordBank::parse("");
+        let drill = Drill { targets: vec!["q".to_string(), "th".to_string()], seed: 9 };
+        let text = drill.generate(&pack.quotes, &bank);
+        let words: Vec<&str> = text.split(' ').collect();
+        assert_eq!(words.len(), DRILL_WORDS);
+        assert!(words.iter().all(|w| w.contains('q') || w.contains("th")));
+        let corpus: HashSet<String> = corpus_words(&pack.quotes).into_iter().collect();
+        assert!(words.iter().all(|w| corpus.contains(*w)), "{}", text);
+        assert_eq!(text, drill.generate(&pack.quotes, &bank));
+    }
+
+    #[test]
+    fn test_corpus_words() {
+        let quotes = parse_corpus("The cat, the DOG. Don't stop: the 42 cats!", "en").unwrap();
+        assert_eq!(corpus_words(&quotes), ["the", "cat", "cats", "dog", "stop"]);
+    }
+
+    #[test]
+    fn test_missing_targets_fall_back_to_the_word_list() {
+        let quotes = parse_corpus("the thin thread", "en").unwrap();
+        let bank = WordBank::parse("the\nzoo\nzebra");
+        let drill = Drill { targets: vec!["th".to_string(), "z".to_string()], seed: 3 };
+        let text = drill.generate(&quotes, &bank);
+        let words: HashSet<&str> = text.split(' ').collect();
+        assert!(words.contains("zoo") || words.contains("zebra"), "{}", text);
+        assert!(words.iter().all(|w| ["the", "thin", "thread", "zoo", "zebra"].contains(w)), "{}", text);
+    }
+
+    #[test]
+    fn test_id_round_trip() {
+        let drill = Drill { targets: vec!["e".to_string(), "th".to_string()], seed: 42 };
+        assert_eq!(drill.id(), "drill-42-e-th");
+        assert_eq!(drill.to_quote(&english(), &WordBank::parse("the")).source.as_deref(), Some("Drill: e, th"));
+        assert_eq!(Drill::from_id(&drill.id()), Some(drill));
+        assert_eq!(Drill::from_id("drill-42"), None);
+        assert_eq!(Drill::from_id("drill-42-THE"), None);
+        assert_eq!(Drill::from_id("words-1k-50-42"), None);
+    }
+}
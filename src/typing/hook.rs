@@ -1,32 +1,89 @@
+use std::rc::Rc;
+
+use gloo_timers::callback::Timeout;
+use wasm_bindgen::JsValue;
 use yew::prelude::*;
+use super::drill::Drill;
 use super::quotes;
-use super::matching::{align_incremental, EditOp};
+use super::sections::{Progress, SectionResult};
+use super::selection;
+use super::transform;
+use super::words::{Mode, Spec, WordOptions};
+use super::matching::{align_incremental, correct_chars, EditOp};
+use super::metrics;
+use super::anticheat::{self, InputSignals, Verdict};
+use super::keybindings::{self, Action, KeyCombo};
+use super::settings::{use_settings, Settings};
+use super::sound::{self, Sound};
+
+// Where the text to type comes from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TextSource {
+    // Random quotes from the selected pack
+    #[default]
+    Quotes,
+    // Sections of user-supplied text, played in order
+    Custom(Rc<Vec<quotes::Quote>>),
+    // The quote of the day, picked from the UTC date, see `selection::quote_of_the_day`
+    Daily,
+}
+
+const FALLBACK_TEXT: &str = "The quick brown fox jumps over the lazy dog.";
 
 pub struct TypingGameReturn {
     pub current_quote: String,
+    pub quote: quotes::Quote,
+    pub pack_id: String,
+    pub select_pack: Callback<String>,
+    // Active transforms, e.g. "Lowercase, Numbers", empty when there are none
+    pub transforms: String,
+    // Set when the quote is a section of a longer text and another section follows
+    pub continue_section: Option<Callback<()>>,
+    // Results of every section of the current text, see `sections::Progress`
+    pub section_results: Vec<Option<SectionResult>>,
+    // Set when quotes are picked from a shared seed
+    pub seed: Option<u64>,
+    // Set on the quote of the day, see `selection::utc_day`
+    pub day: Option<u64>,
+    // Set when the quote asked for in the URL no longer exists
+    pub missing_quote_id: Option<String>,
     pub user_input: String,
     #[allow(dead_code)]
     pub current_position: usize,
     #[allow(dead_code)]
     pub started: bool,
     pub finished: bool,
-    pub scroll_offset: usize, // Expose scroll_offset
+    pub paused: bool,
+    // When the current pause started
+    pub paused_at: Option<f64>,
+    // Set in timed mode
+    pub time_limit_ms: Option<f64>,
+    pub show_overlays: bool,
     pub on_keydown: Callback<web_sys::KeyboardEvent>,
+    pub on_paste: Callback<web_sys::Event>,
+    pub on_drop: Callback<web_sys::DragEvent>,
     pub div_ref: NodeRef,
     #[allow(dead_code)]
     pub reset: Callback<()>,
-    pub set_scroll_offset: Callback<usize>, // Expose setter
+    #[allow(dead_code)]
+    pub restart: Callback<()>,
+    // Starts a drill on the given weak spots, see `drill::weak_spots`
+    pub start_drill: Callback<Vec<String>>,
     
     // Stats & Data
     pub error_count: usize,
-    #[allow(dead_code)]
     pub total_typed_chars: usize,
     pub keystroke_times: Vec<f64>,
+    // Gaps between every character typed, see `InputSignals::intervals`
+    pub typed_intervals: Vec<f64>,
     pub start_time: Option<f64>,
     #[allow(dead_code)]
     pub end_time: Option<f64>,
     pub error_positions: Vec<usize>,
     pub key_log: String,
+    // The key just typed, if it was a mistake, for the on-screen keyboard
+    pub mistyped_key: Option<char>,
+    pub verdict: Verdict,
     
     // Pre-calculated stats
     pub wpm: f64,
@@ -38,21 +95,45 @@ pub struct TypingGameReturn {
 }
 
 #[hook]
-pub fn use_typing_game() -> TypingGameReturn {
+pub fn use_typing_game(source: TextSource) -> TypingGameReturn {
     let quote_context = use_context::<Option<crate::QuoteContext>>().flatten();
+    let settings_context = use_settings();
+    let settings = settings_context.settings;
+    let key_bindings = settings.key_bindings.clone();
     
-    let current_quote = use_state(|| {
-        if let Some(ctx) = quote_context {
-            return quotes::QUOTES[ctx.index % quotes::QUOTES.len()].to_string();
+    let linked = quote_context.as_ref().is_some_and(|ctx| ctx.linked);
+    let initial_pack = quotes::pack_source_or_default(quote_context.as_ref().map(|ctx| ctx.pack.as_str()));
+    let pack_id = use_state(|| initial_pack.id.to_string());
+    let seed = use_state(|| quote_context.as_ref().and_then(|ctx| ctx.seed));
+    // Only set when the server rendered the daily page, otherwise worked out once mounted
+    let day = use_state(|| quote_context.as_ref().and_then(|ctx| ctx.day));
+    // The quote as loaded, before the transforms
+    let original_quote = {
+        let quote_context = quote_context.clone();
+        let source = source.clone();
+        use_state(move || match &source {
+            TextSource::Custom(sections) => sections[0].clone(),
+            // The server picks the first quote and inlines it into the page
+            TextSource::Quotes | TextSource::Daily => quote_context.map(|ctx| ctx.quote).unwrap_or_else(fallback_quote),
+        })
+    };
+    // The quote as played
+    let current_quote = use_memo(
+        ((*original_quote).clone(), settings.transforms.clone()),
+        |(quote, transforms)| transform::apply(transforms, quote),
+    );
+    let missing_quote_id = use_state(|| {
+        if source != TextSource::Quotes {
+            return None;
         }
-        let idx = (js_sys::Math::random() * quotes::QUOTES.len() as f64) as usize;
-        quotes::QUOTES[idx].to_string()
+        quote_context.and_then(|ctx| ctx.missing_id)
     });
     let user_input = use_state(|| String::new());
     let current_position = use_state(|| 0usize); // Track position in quote
     let started = use_state(|| false);
     let finished = use_state(|| false);
-    let scroll_offset = use_state(|| 0usize); // Added scroll_offset
+    let paused_at = use_state(|| None::<f64>); // Set while the session is paused
+    let show_overlays = use_state(|| true);
     let start_time = use_state(|| None::<f64>);
     let end_time = use_state(|| None::<f64>);
     let error_count = use_state(|| 0usize);
@@ -61,8 +142,24 @@ pub fn use_typing_game() -> TypingGameReturn {
     let error_positions = use_state(|| Vec::<usize>::new()); // Positions where errors occurred
     let total_typed_chars = use_state(|| 0usize); // Total characters typed (including errors)
     let key_log = use_state(|| String::new()); // Log of all keys pressed
+    let mistyped_key = use_state(|| None::<char>); // The last key typed, if it was a mistake
+    let input_signals = use_state(InputSignals::default); // Anti-cheat signals
     let div_ref = use_node_ref();
 
+    // Keep the URL pointing at the current quote, so it can be shared or bookmarked.
+    // Custom text only exists in this page, so there's nothing to link to, and the quote
+    // of the day already has its own URL.
+    let linkable = source == TextSource::Quotes;
+    use_effect_with(
+        (linkable, (*pack_id).clone(), current_quote.id.clone(), *seed),
+        |(linkable, pack_id, id, seed)| {
+            if *linkable {
+                replace_quote_in_url(pack_id, id, *seed);
+            }
+            || ()
+        },
+    );
+
     // Auto-focus on mount
     {
         let div_ref = div_ref.clone();
@@ -74,37 +171,39 @@ pub fn use_typing_game() -> TypingGameReturn {
         });
     }
 
-    let reset = {
+    // Clears progress on the current quote
+    let restart = {
         let user_input = user_input.clone();
         let current_position = current_position.clone();
         let started = started.clone();
         let finished = finished.clone();
-        let scroll_offset = scroll_offset.clone(); // Capture scroll_offset
         let start_time = start_time.clone();
         let end_time = end_time.clone();
-        let current_quote = current_quote.clone();
+        let paused_at = paused_at.clone();
         let error_count = error_count.clone();
         let keystroke_times = keystroke_times.clone();
         let error_positions = error_positions.clone();
         let total_typed_chars = total_typed_chars.clone();
         let key_log = key_log.clone();
+        let mistyped_key = mistyped_key.clone();
+        let input_signals = input_signals.clone();
         let div_ref = div_ref.clone();
 
         Callback::from(move |_| {
-            let idx = (js_sys::Math::random() * quotes::QUOTES.len() as f64) as usize;
-            current_quote.set(quotes::QUOTES[idx].to_string());
-            scroll_offset.set(0); // Reset scroll_offset
             user_input.set(String::new());
             current_position.set(0);
             started.set(false);
             finished.set(false);
             start_time.set(None);
             end_time.set(None);
+            paused_at.set(None);
             error_count.set(0);
             keystroke_times.set(Vec::new());
             error_positions.set(Vec::new());
             total_typed_chars.set(0);
             key_log.set(String::new());
+            mistyped_key.set(None);
+            input_signals.set(InputSignals::default());
 
             // Re-focus after reset
             if let Some(element) = div_ref.cast::<web_sys::HtmlElement>() {
@@ -113,11 +212,192 @@ pub fn use_typing_game() -> TypingGameReturn {
         })
     };
 
+    // Replaces the quote and starts over on it
+    let show_quote = {
+        let original_quote = original_quote.clone();
+        let missing_quote_id = missing_quote_id.clone();
+        let restart = restart.clone();
+
+        Callback::from(move |quote: quotes::Quote| {
+            missing_quote_id.set(None);
+            original_quote.set(quote);
+            restart.emit(());
+        })
+    };
+
+    // The server only picks the quote of the day when it renders the daily page. After
+    // navigating here within the app, or if the page was left open past midnight, the
+    // quote in the context is some other one, so ask for today's.
+    {
+        let day = day.clone();
+        let pack_id = pack_id.clone();
+        let show_quote = show_quote.clone();
+        use_effect_with(source == TextSource::Daily, move |daily| {
+            let today = selection::utc_day(js_sys::Date::now());
+            if *daily && *day != Some(today) {
+                day.set(Some(today));
+                let request = quotes::QuoteRequest {
+                    pack: Some((*pack_id).clone()),
+                    daily: Some(today),
+                    ..Default::default()
+                };
+                fetch_quote(request, show_quote);
+            }
+            || ()
+        });
+    }
+
+    // Changing the transforms changes the text, so progress on it no longer counts
+    {
+        let restart = restart.clone();
+        let first_run = use_mut_ref(|| true);
+        use_effect_with(settings.transforms.clone(), move |_| {
+            if !std::mem::replace(&mut *first_run.borrow_mut(), false) {
+                restart.emit(());
+            }
+            || ()
+        });
+    }
+
+    // Starts over with a new random quote, the next one in a seeded sequence, or the next
+    // section of custom text. There is one quote of the day, so that one is just retried.
+    let reset = {
+        let current_id = current_quote.id.clone();
+        let current_section = current_quote.section.clone();
+        let pack_id = pack_id.clone();
+        let seed = seed.clone();
+        let show_quote = show_quote.clone();
+        let restart = restart.clone();
+        let settings = settings.clone();
+        let source = source.clone();
+
+        Callback::from(move |_| match &source {
+            TextSource::Quotes => {
+                let next_seed = seed.map(selection::next_seed);
+                seed.set(next_seed);
+                load_next_quote(&pack_id, &settings, Some(&current_id), next_seed, show_quote.clone());
+            }
+            TextSource::Custom(sections) => {
+                let next = current_section.as_ref().map_or(0, |s| (s.index + 1) % sections.len());
+                show_quote.emit(sections[next].clone());
+            }
+            TextSource::Daily => restart.emit(()),
+        })
+    };
+
+    // Moves on to the next section of a long text
+    let continue_section = current_quote.section.as_ref().and_then(|s| s.next_id()).map(|next_id| {
+        let next_index = current_quote.section.as_ref().map_or(0, |s| s.index + 1);
+        let pack_id = pack_id.clone();
+        let show_quote = show_quote.clone();
+        let source = source.clone();
+
+        Callback::from(move |_| match &source {
+            TextSource::Custom(sections) => show_quote.emit(sections[next_index].clone()),
+            TextSource::Quotes | TextSource::Daily => {
+                fetch_quote(quotes::QuoteRequest::by_id(&pack_id, &next_id), show_quote.clone());
+            }
+        })
+    });
+
+    // Switches to another pack and starts a quote from it
+    let select_pack = {
+        let pack_id = pack_id.clone();
+        let seed = seed.clone();
+        let show_quote = show_quote.clone();
+        let settings = settings.clone();
+
+        Callback::from(move |id: String| {
+            let pack = quotes::pack_source_or_default(Some(&id));
+            pack_id.set(pack.id.to_string());
+            load_next_quote(pack.id, &settings, None, *seed, show_quote.clone());
+        })
+    };
+
+    let start_drill = {
+        let pack_id = pack_id.clone();
+        let show_quote = show_quote.clone();
+        Callback::from(move |targets: Vec<String>| {
+            let id = Drill { targets, seed: random_seed() }.id();
+            fetch_quote(quotes::QuoteRequest::by_id(&pack_id, &id), show_quote.clone());
+        })
+    };
+
+    // Switch between quotes and generated words when the mode changes, including when the
+    // stored settings are first read. A quote opened from a link, or a seeded sequence, is
+    // kept until the user picks another mode, which leaves the sequence.
+    {
+        let reset = reset.clone();
+        let seed = seed.clone();
+        let pack_id = pack_id.clone();
+        let show_quote = show_quote.clone();
+        let settings = settings.clone();
+        let current_id = current_quote.id.clone();
+        let first_check = use_mut_ref(|| true);
+        let active = settings_context.loaded && source == TextSource::Quotes;
+        use_effect_with((active, settings.mode, settings.word_options), move |(active, mode, options)| {
+            if *active {
+                let first = std::mem::replace(&mut *first_check.borrow_mut(), false);
+                let keep = first && (linked || seed.is_some());
+                if !keep && !fits_mode(&current_id, *mode, *options) {
+                    if seed.is_some() {
+                        seed.set(None);
+                        load_next_quote(&pack_id, &settings, Some(&current_id), None, show_quote.clone());
+                    } else {
+                        reset.emit(());
+                    }
+                }
+            }
+            || ()
+        });
+    }
+
+    // Timed mode ends the test when the time is up. The deadline moves with the clock
+    // after a pause, so the timer is set again whenever the start time changes.
+    // Seeded sequences are always untimed quotes, so everyone on the seed plays the same test
+    let time_limit = if source == TextSource::Quotes && seed.is_none() { settings.mode.time_limit_ms() } else { None };
+    {
+        let finished = finished.clone();
+        let started = started.clone();
+        let end_time = end_time.clone();
+        use_effect_with(
+            (time_limit, *start_time, paused_at.is_some(), *started),
+            move |(time_limit, start, paused, running)| {
+                let timeout = match (*time_limit, *start) {
+                    (Some(limit), Some(start)) if *running && !*paused => {
+                        let deadline = start + limit;
+                        let delay = (deadline - js_sys::Date::now()).max(0.0) as u32;
+                        Some(Timeout::new(delay, move || {
+                            finished.set(true);
+                            end_time.set(Some(deadline));
+                            started.set(false);
+                        }))
+                    }
+                    _ => None,
+                };
+                move || drop(timeout)
+            },
+        );
+    }
+
+    // Set up on the first keystroke, see `sound::Player`
+    let sound_player = use_mut_ref(|| None);
+    {
+        let sound_player = sound_player.clone();
+        use_effect_with((), move |_| move || sound::stop(&sound_player));
+    }
+
     let on_keydown = {
+        let sound_player = sound_player.clone();
+        let sound_options = settings.sound;
+        // The error sound would give away what blind mode hides
+        let error_sound = settings.display_policy.shows_errors();
         let user_input = user_input.clone();
         let current_position = current_position.clone();
         let started = started.clone();
         let finished = finished.clone();
+        let paused_at = paused_at.clone();
+        let show_overlays = show_overlays.clone();
         let start_time = start_time.clone();
         let end_time = end_time.clone();
         let current_quote = current_quote.clone();
@@ -126,31 +406,56 @@ pub fn use_typing_game() -> TypingGameReturn {
         let error_positions = error_positions.clone();
         let total_typed_chars = total_typed_chars.clone();
         let key_log = key_log.clone();
+        let mistyped_key = mistyped_key.clone();
+        let input_signals = input_signals.clone();
         let reset = reset.clone();
+        let restart = restart.clone();
 
         Callback::from(move |e: web_sys::KeyboardEvent| {
             let key = e.key();
 
-            // Tab always starts new quote
-            if key == "Tab" {
-                e.prevent_default();
-                reset.emit(());
-                return;
+            // Events dispatched by scripts are still processed, but the run is marked unverified
+            let mut signals = (*input_signals).clone();
+            if !e.is_trusted() {
+                signals.untrusted_events += 1;
+                input_signals.set(signals.clone());
             }
 
-            // Escape: finish early if started, otherwise reset
-            if key == "Escape" {
-                e.prevent_default();
-                if *started {
-                    finished.set(true);
-                    // Use the last keystroke time instead of now
-                    let last_keystroke_time = keystroke_times.last().copied();
-                    end_time.set(last_keystroke_time.or(Some(js_sys::Date::now())));
-                    started.set(false);
-                } else {
+            let pressed = KeyCombo::from_event(&e);
+            let action = key_bindings.action_for(&pressed);
+
+            match action {
+                Some(Action::NewQuote) => {
+                    e.prevent_default();
                     reset.emit(());
+                    return;
                 }
-                return;
+                Some(Action::Restart) => {
+                    e.prevent_default();
+                    restart.emit(());
+                    return;
+                }
+                // Finish early if started, otherwise reset
+                Some(Action::Finish) => {
+                    e.prevent_default();
+                    if *started {
+                        finished.set(true);
+                        // Use the last keystroke time instead of now
+                        let last_keystroke_time = keystroke_times.last().copied();
+                        end_time.set(last_keystroke_time.or(Some(js_sys::Date::now())));
+                        started.set(false);
+                        paused_at.set(None);
+                    } else {
+                        reset.emit(());
+                    }
+                    return;
+                }
+                Some(Action::ToggleOverlays) => {
+                    e.prevent_default();
+                    show_overlays.set(!*show_overlays);
+                    return;
+                }
+                _ => {}
             }
 
             // If finished, don't process other keys
@@ -158,42 +463,81 @@ pub fn use_typing_game() -> TypingGameReturn {
                 return;
             }
 
-            // Handle backspace
-            if key == "Backspace" {
-                e.prevent_default();
-                
-                // Log backspace
-                let mut log = (*key_log).clone();
-                if e.ctrl_key() {
-                    log.push_str("[Ctrl+BS]");
-                } else {
-                    log.push_str("[BS]");
+            let is_delete = matches!(action, Some(Action::DeleteChar | Action::DeleteWord | Action::DeleteLine));
+            if !is_delete && action != Some(Action::Pause) && !keybindings::types_char(&key) {
+                // Only process single character keys
+                return;
+            }
+            e.prevent_default();
+
+            let now = js_sys::Date::now();
+
+            // Shift the clock forward by the paused duration, so the pause doesn't count
+            // towards elapsed time or the gaps between keystrokes.
+            let mut times = (*keystroke_times).clone();
+            if let Some(paused) = *paused_at {
+                let pause_len = now - paused;
+                for t in times.iter_mut() {
+                    *t += pause_len;
+                }
+                start_time.set(start_time.map(|s| s + pause_len));
+                keystroke_times.set(times.clone());
+                if let Some(last) = signals.last_keystroke.as_mut() {
+                    *last += pause_len;
                 }
+                input_signals.set(signals.clone());
+                paused_at.set(None);
+                if action == Some(Action::Pause) {
+                    return;
+                }
+            } else if action == Some(Action::Pause) {
+                // Nothing to pause before the first keystroke
+                if *started {
+                    paused_at.set(Some(now));
+                }
+                return;
+            }
+
+            // Handle deletion
+            if is_delete {
+                sound::play(&sound_player, &sound_options, Sound::Key(sound_options.profile));
+                mistyped_key.set(None);
+
+                // Log deletion as the combo that was pressed, e.g. "[Ctrl+W]"
+                let mut log = (*key_log).clone();
+                log.push_str(&format!("[{}]", pressed));
                 key_log.set(log);
 
                 if *current_position > 0 {
                     let mut current = (*user_input).clone();
 
-                    if e.ctrl_key() {
-                        // Ctrl+Backspace: Delete word
-                        // 1. Remove trailing whitespace
-                        while let Some(c) = current.chars().last() {
-                            if c.is_whitespace() {
-                                current.pop();
-                            } else {
-                                break;
+                    match action {
+                        Some(Action::DeleteWord) => {
+                            // 1. Remove trailing whitespace
+                            while let Some(c) = current.chars().last() {
+                                if c.is_whitespace() {
+                                    current.pop();
+                                } else {
+                                    break;
+                                }
                             }
-                        }
-                        // 2. Remove trailing non-whitespace
-                        while let Some(c) = current.chars().last() {
-                            if !c.is_whitespace() {
-                                current.pop();
-                            } else {
-                                break;
+                            // 2. Remove trailing non-whitespace
+                            while let Some(c) = current.chars().last() {
+                                if !c.is_whitespace() {
+                                    current.pop();
+                                } else {
+                                    break;
+                                }
                             }
                         }
-                    } else {
-                        current.pop();
+                        // Quotes are a single line, so this clears everything typed so far
+                        Some(Action::DeleteLine) => {
+                            let line_start = current.rfind('\n').map(|i| i + 1).unwrap_or(0);
+                            current.truncate(line_start);
+                        }
+                        _ => {
+                            current.pop();
+                        }
                     }
 
                     let new_len = current.chars().count();
@@ -201,7 +545,6 @@ pub fn use_typing_game() -> TypingGameReturn {
                     current_position.set(new_len);
 
                     // Sync keystroke times
-                    let mut times = (*keystroke_times).clone();
                     if times.len() > new_len {
                         times.truncate(new_len);
                         keystroke_times.set(times);
@@ -210,20 +553,11 @@ pub fn use_typing_game() -> TypingGameReturn {
                 return;
             }
 
-            // Only process single character keys
-            if key.len() != 1 {
-                return;
-            }
-
-            e.prevent_default();
-
             // Log key
             let mut log = (*key_log).clone();
             log.push_str(&key);
             key_log.set(log);
 
-            let now = js_sys::Date::now();
-
             if !*started {
                 started.set(true);
                 start_time.set(Some(now));
@@ -233,9 +567,10 @@ pub fn use_typing_game() -> TypingGameReturn {
             total_typed_chars.set(*total_typed_chars + 1);
 
             // Record keystroke time
-            let mut times = (*keystroke_times).clone();
             times.push(now);
             keystroke_times.set(times.clone());
+            signals.record_keystroke(now);
+            input_signals.set(signals);
 
             let mut current = (*user_input).clone();
             current.push_str(&key);
@@ -245,7 +580,7 @@ pub fn use_typing_game() -> TypingGameReturn {
             // Run alignment to check for errors and completion
             // We use the dynamic alignment to determine if the LAST typed char was an error.
             // This handles skips correctly (skipping text doesn't make subsequent correct typing an error).
-            let alignment = align_incremental(&current_quote, &current);
+            let alignment = align_incremental(&current_quote.text, &current);
 
             // Find the index of the operation corresponding to the last input character
             let mut last_input_idx = None;
@@ -273,6 +608,9 @@ pub fn use_typing_game() -> TypingGameReturn {
                     EditOp::Substitute => true,
                     EditOp::Insert => true,
                 };
+                let played = if is_error && error_sound { Sound::Error } else { Sound::Key(sound_options.profile) };
+                sound::play(&sound_player, &sound_options, played);
+                mistyped_key.set(if is_error { key.chars().next() } else { None });
 
                 if is_error {
                     error_count.set(*error_count + 1);
@@ -294,7 +632,7 @@ pub fn use_typing_game() -> TypingGameReturn {
 
             // Quote is finished if we consumed all chars (matches + skips)
             // But we also want to ensure the user is at the end of their typing (implied)
-            let quote_len = current_quote.chars().count();
+            let quote_len = current_quote.text.chars().count();
             if consumed_quote_chars >= quote_len {
                  finished.set(true);
                  // Use the last keystroke time (which is `now` that we just pushed)
@@ -304,32 +642,38 @@ pub fn use_typing_game() -> TypingGameReturn {
         })
     };
 
+    // Paste and drop never reach the input, but are recorded for the verdict
+    let on_paste = {
+        let input_signals = input_signals.clone();
+        Callback::from(move |e: web_sys::Event| {
+            e.prevent_default();
+            let mut signals = (*input_signals).clone();
+            signals.paste_attempts += 1;
+            input_signals.set(signals);
+        })
+    };
+
+    let on_drop = {
+        let input_signals = input_signals.clone();
+        Callback::from(move |e: web_sys::DragEvent| {
+            e.prevent_default();
+            let mut signals = (*input_signals).clone();
+            signals.drop_attempts += 1;
+            input_signals.set(signals);
+        })
+    };
+
+    let verdict = anticheat::assess(&input_signals);
+
     // Calculate statistics
-    let total_chars = current_quote.chars().count();
-    let total_words = current_quote.split_whitespace().count();
+    let total_chars = current_quote.text.chars().count();
+    let total_words = current_quote.text.split_whitespace().count();
 
     let (wpm, cpm, accuracy, elapsed_seconds) = if *finished {
         if let (Some(start), Some(end)) = (*start_time, *end_time) {
             let elapsed_ms = end - start;
-            let elapsed_sec = elapsed_ms / 1000.0;
-            let elapsed_min = elapsed_sec / 60.0;
-
-            // Count only correct characters using alignment
-            let alignment = align_incremental(&current_quote, &user_input);
-            let correct_chars = alignment.iter()
-                .filter(|(op, _, _)| *op == EditOp::Match)
-                .count();
-
-            let cpm = if elapsed_min > 0.0 { correct_chars as f64 / elapsed_min } else { 0.0 };
-            let wpm = if elapsed_min > 0.0 { (correct_chars as f64 / 5.0) / elapsed_min } else { 0.0 };
-
-            let accuracy = if *total_typed_chars > 0 {
-                (1.0 - (*error_count as f64 / *total_typed_chars as f64)) * 100.0
-            } else {
-                100.0
-            };
-
-            (wpm, cpm, accuracy.max(0.0), elapsed_sec)
+            let (wpm, cpm) = metrics::speed(correct_chars(&current_quote.text, &user_input), elapsed_ms);
+            (wpm, cpm, metrics::accuracy(*error_count, *total_typed_chars), elapsed_ms / 1000.0)
         } else {
             (0.0, 0.0, 0.0, 0.0)
         }
@@ -337,29 +681,60 @@ pub fn use_typing_game() -> TypingGameReturn {
         (0.0, 0.0, 0.0, 0.0)
     };
 
-    let set_scroll_offset = {
-        let scroll_offset = scroll_offset.clone();
-        Callback::from(move |offset| scroll_offset.set(offset))
-    };
+    // Keep the result of each finished section, so a long text can be typed over
+    // several sessions
+    let section_results = use_state(Vec::<Option<SectionResult>>::new);
+    {
+        let section_results = section_results.clone();
+        let section = current_quote.section.clone();
+        use_effect_with((*finished, section), move |(finished, section)| {
+            if let (true, Some(section)) = (*finished, section) {
+                let mut progress = Progress::load();
+                progress.record(section, SectionResult { wpm, accuracy });
+                progress.save();
+                section_results.set(progress.results(section));
+            }
+            || ()
+        });
+    }
+
 
     TypingGameReturn {
-        current_quote: (*current_quote).clone(),
+        current_quote: current_quote.text.clone(),
+        quote: (*current_quote).clone(),
+        pack_id: (*pack_id).clone(),
+        select_pack,
+        continue_section,
+        section_results: (*section_results).clone(),
+        transforms: transform::describe(&settings.transforms),
+        seed: if linkable { *seed } else { None },
+        day: if source == TextSource::Daily { *day } else { None },
+        missing_quote_id: (*missing_quote_id).clone(),
         user_input: (*user_input).clone(),
         current_position: *current_position,
         started: *started,
         finished: *finished,
-        scroll_offset: *scroll_offset,
+        paused: paused_at.is_some(),
+        paused_at: *paused_at,
+        time_limit_ms: time_limit,
+        show_overlays: *show_overlays,
         on_keydown,
+        on_paste,
+        on_drop,
         div_ref,
         reset,
-        set_scroll_offset,
+        restart,
+        start_drill,
         error_count: *error_count,
         total_typed_chars: *total_typed_chars,
         keystroke_times: (*keystroke_times).clone(),
+        typed_intervals: input_signals.intervals.clone(),
         start_time: *start_time,
         end_time: *end_time,
         error_positions: (*error_positions).clone(),
         key_log: (*key_log).clone(),
+        mistyped_key: *mistyped_key,
+        verdict,
         wpm,
         cpm,
         accuracy,
@@ -368,3 +743,75 @@ pub fn use_typing_game() -> TypingGameReturn {
         total_words,
     }
 }
+
+// Freshly generated words, or a quote from the pack, depending on the mode. A seed picks
+// the next quote of the shared sequence whatever the settings. The server holds the word
+// list, so generated tests are asked for by id like any other quote.
+fn load_next_quote(
+    pack_id: &str,
+    settings: &Settings,
+    exclude: Option<&str>,
+    seed: Option<u64>,
+    show: Callback<quotes::Quote>,
+) {
+    if let Some(seed) = seed {
+        fetch_quote(quotes::QuoteRequest::seeded(pack_id, seed), show);
+        return;
+    }
+    if let Some(count) = settings.mode.word_count() {
+        let id = Spec { options: settings.word_options, count, seed: random_seed() }.id();
+        fetch_quote(quotes::QuoteRequest::by_id(pack_id, &id), show);
+        return;
+    }
+    fetch_quote(quotes::QuoteRequest::random(pack_id, &settings.quote_filter, exclude), show);
+}
+
+fn fetch_quote(request: quotes::QuoteRequest, show: Callback<quotes::Quote>) {
+    let url = request.url();
+    yew::platform::spawn_local(async move {
+        match fetch_json::<quotes::Quote>(&url).await {
+            Ok(quote) => show.emit(quote),
+            Err(e) => log::warn!("failed to load a quote: {}", e),
+        }
+    });
+}
+
+pub async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, gloo_net::Error> {
+    gloo_net::http::Request::get(url).send().await?.json().await
+}
+
+// Used when the page wasn't rendered by the server, so there is no quote to start with
+fn fallback_quote() -> quotes::Quote {
+    quotes::Quote::from_text(None, FALLBACK_TEXT, "en")
+}
+
+fn random_seed() -> u64 {
+    (js_sys::Math::random() * u32::MAX as f64) as u64
+}
+
+// Whether the quote is what the mode asks for
+fn fits_mode(quote_id: &str, mode: Mode, options: WordOptions) -> bool {
+    match (mode.word_count(), Spec::from_id(quote_id)) {
+        (None, spec) => spec.is_none(),
+        (Some(count), Some(spec)) => spec.count == count && spec.options == options,
+        (Some(_), None) => false,
+    }
+}
+
+fn replace_quote_in_url(pack_id: &str, id: &str, seed: Option<u64>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let Ok(url) = window.location().href().and_then(|href| web_sys::Url::new(&href)) else {
+        return;
+    };
+    url.search_params().set("pack", pack_id);
+    url.search_params().set("quote", id);
+    match seed {
+        Some(seed) => url.search_params().set("seed", &seed.to_string()),
+        None => url.search_params().delete("seed"),
+    }
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&JsValue::NULL, "", Some(&url.href()));
+    }
+}
@@ -0,0 +1,162 @@
+// Live stats shown above the text while typing.
+//
+// The numbers are recomputed on a timer rather than on every keystroke, so fast typing
+// doesn't redo the alignment for each key and the figures don't flicker. They use the
+// same formulas as the results, see `metrics::speed`.
+
+use gloo_timers::callback::Interval;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use super::matching::correct_chars;
+use super::metrics;
+use super::theme::use_theme;
+
+const REFRESH_MS: u32 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudOptions {
+    pub enabled: bool,
+    pub wpm: bool,
+    pub accuracy: bool,
+    pub errors: bool,
+    // Elapsed time, or the time left in timed mode
+    pub timer: bool,
+}
+
+impl Default for HudOptions {
+    fn default() -> Self {
+        HudOptions { enabled: false, wpm: true, accuracy: true, errors: true, timer: true }
+    }
+}
+
+// Everything the stats are worked out from
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HudInput {
+    pub quote: String,
+    pub input: String,
+    pub start_time: Option<f64>,
+    pub paused_at: Option<f64>,
+    pub error_count: usize,
+    pub typed_chars: usize,
+    pub time_limit_ms: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub errors: usize,
+    pub elapsed_ms: f64,
+    pub remaining_ms: Option<f64>,
+}
+
+pub fn snapshot(input: &HudInput, now: f64) -> Snapshot {
+    // The clock stands still while paused
+    let elapsed_ms = input.start_time.map_or(0.0, |start| (input.paused_at.unwrap_or(now) - start).max(0.0));
+    let (wpm, _) = metrics::speed(correct_chars(&input.quote, &input.input), elapsed_ms);
+    Snapshot {
+        wpm,
+        accuracy: metrics::accuracy(input.error_count, input.typed_chars),
+        errors: input.error_count,
+        elapsed_ms,
+        remaining_ms: input.time_limit_ms.map(|limit| (limit - elapsed_ms).max(0.0)),
+    }
+}
+
+// e.g. "1:05", rounding up so a countdown shows 0:00 only when the time is up
+pub fn format_clock(ms: f64) -> String {
+    let seconds = (ms / 1000.0).ceil().max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct StatsHudProps {
+    pub input: HudInput,
+    pub options: HudOptions,
+}
+
+#[function_component]
+pub fn StatsHud(props: &StatsHudProps) -> Html {
+    let theme = use_theme();
+    // Read by the timer, so it always sees the latest input without restarting
+    let latest = use_mut_ref(HudInput::default);
+    *latest.borrow_mut() = props.input.clone();
+    let shown = use_state_eq(|| snapshot(&props.input, props.input.start_time.unwrap_or_default()));
+    {
+        let shown = shown.clone();
+        use_effect_with((), move |_| {
+            let interval = Interval::new(REFRESH_MS, move || {
+                shown.set(snapshot(&latest.borrow(), js_sys::Date::now()));
+            });
+            move || drop(interval)
+        });
+    }
+
+    let options = props.options;
+    let clock = shown.remaining_ms.unwrap_or(shown.elapsed_ms);
+    html! {
+        <div class="flex justify-center gap-8 mb-4 font-mono text-xl" style={format!("color: {};", theme.caret)}>
+            if options.wpm {
+                <span title="Words per minute">{format!("{:.0} wpm", shown.wpm)}</span>
+            }
+            if options.accuracy {
+                <span title="Accuracy">{format!("{:.0}%", shown.accuracy)}</span>
+            }
+            if options.errors {
+                <span title="Errors">{format!("{} errors", shown.errors)}</span>
+            }
+            if options.timer {
+                <span title={if shown.remaining_ms.is_some() { "Time left" } else { "Time" }}>{format_clock(clock)}</span>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input() -> HudInput {
+        HudInput {
+            quote: "hello world".to_string(),
+            input: "hello".to_string(),
+            start_time: Some(1_000.0),
+            typed_chars: 6,
+            error_count: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        // 5 correct characters in 6 seconds = 50 CPM = 10 WPM
+        let shown = snapshot(&input(), 7_000.0);
+        assert_eq!(shown.elapsed_ms, 6_000.0);
+        assert!((shown.wpm - 10.0).abs() < 1e-9);
+        assert!((shown.accuracy - 500.0 / 6.0).abs() < 1e-9);
+        assert_eq!(shown.errors, 1);
+        assert_eq!(shown.remaining_ms, None);
+
+        let not_started = snapshot(&HudInput { start_time: None, ..input() }, 7_000.0);
+        assert_eq!((not_started.elapsed_ms, not_started.wpm), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_snapshot_timed_and_paused() {
+        let timed = HudInput { time_limit_ms: Some(15_000.0), ..input() };
+        assert_eq!(snapshot(&timed, 7_000.0).remaining_ms, Some(9_000.0));
+        assert_eq!(snapshot(&timed, 60_000.0).remaining_ms, Some(0.0));
+
+        let paused = HudInput { paused_at: Some(3_000.0), ..timed };
+        assert_eq!(snapshot(&paused, 60_000.0).elapsed_ms, 2_000.0);
+    }
+
+    #[test]
+    fn test_format_clock() {
+        assert_eq!(format_clock(0.0), "0:00");
+        assert_eq!(format_clock(65_000.0), "1:05");
+        assert_eq!(format_clock(14_200.0), "0:15");
+    }
+}
@@ -0,0 +1,337 @@
+// Key bindings for session control.
+// Bindings are stored as human-readable combos ("Ctrl+Shift+Backspace") so the
+// persisted settings stay editable by hand.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    NewQuote,
+    Restart,
+    Finish,
+    Pause,
+    DeleteChar,
+    DeleteWord,
+    DeleteLine,
+    ToggleOverlays,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::NewQuote,
+        Action::Restart,
+        Action::Finish,
+        Action::Pause,
+        Action::DeleteChar,
+        Action::DeleteWord,
+        Action::DeleteLine,
+        Action::ToggleOverlays,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::NewQuote => "New quote",
+            Action::Restart => "Restart",
+            Action::Finish => "Finish",
+            Action::Pause => "Pause",
+            Action::DeleteChar => "Delete character",
+            Action::DeleteWord => "Delete word",
+            Action::DeleteLine => "Delete line",
+            Action::ToggleOverlays => "Toggle overlays",
+        }
+    }
+}
+
+// Whether a `KeyboardEvent.key` types a character, e.g. "a" or "ß", rather than naming a
+// key like "Shift". Counts characters, not bytes, so accented letters aren't dropped.
+pub fn types_char(key: &str) -> bool {
+    key.chars().count() == 1
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyCombo {
+    pub key: String,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+impl KeyCombo {
+    pub fn from_event(e: &web_sys::KeyboardEvent) -> Self {
+        KeyCombo {
+            key: e.key(),
+            ctrl: e.ctrl_key(),
+            alt: e.alt_key(),
+            shift: e.shift_key(),
+            meta: e.meta_key(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut combo = KeyCombo {
+            key: String::new(),
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+        };
+        // The key itself may be "+", so only split on separators that are followed by something.
+        let parts: Vec<&str> = match s.trim().strip_suffix("++") {
+            Some(prefix) => prefix.split('+').chain(std::iter::once("+")).collect(),
+            None => s.trim().split('+').collect(),
+        };
+        let (key, modifiers) = parts.split_last().ok_or("empty key binding")?;
+        for modifier in modifiers {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" | "option" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "meta" | "cmd" | "super" => combo.meta = true,
+                other => return Err(format!("unknown modifier '{}'", other)),
+            }
+        }
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("missing key in '{}'", s));
+        }
+        combo.key = match key.to_ascii_lowercase().as_str() {
+            "esc" => "Escape".to_string(),
+            "space" => " ".to_string(),
+            _ => key.to_string(),
+        };
+        Ok(combo)
+    }
+
+    // Letter keys report "W" when shift is held, so compare them case-insensitively.
+    pub fn matches(&self, pressed: &KeyCombo) -> bool {
+        self.key.eq_ignore_ascii_case(&pressed.key)
+            && self.ctrl == pressed.ctrl
+            && self.alt == pressed.alt
+            && self.shift == pressed.shift
+            && self.meta == pressed.meta
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.meta {
+            write!(f, "Meta+")?;
+        }
+        match self.key.as_str() {
+            " " => write!(f, "Space"),
+            key => write!(f, "{}", key),
+        }
+    }
+}
+
+impl TryFrom<String> for KeyCombo {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        KeyCombo::parse(&s)
+    }
+}
+
+impl From<KeyCombo> for String {
+    fn from(combo: KeyCombo) -> Self {
+        combo.to_string()
+    }
+}
+
+// An action with no combos has been unbound on purpose. Actions missing from stored
+// settings, e.g. ones added since they were saved, get their default bindings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BTreeMap<Action, Vec<KeyCombo>>")]
+pub struct KeyBindings(BTreeMap<Action, Vec<KeyCombo>>);
+
+impl From<BTreeMap<Action, Vec<KeyCombo>>> for KeyBindings {
+    fn from(mut stored: BTreeMap<Action, Vec<KeyCombo>>) -> Self {
+        for (action, combos) in KeyBindings::default().0 {
+            stored.entry(action).or_insert(combos);
+        }
+        KeyBindings(stored)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let defaults: [(Action, &[&str]); 8] = [
+            (Action::NewQuote, &["Tab"]),
+            (Action::Restart, &["Ctrl+Enter"]),
+            (Action::Finish, &["Escape"]),
+            (Action::Pause, &["Ctrl+Shift+P"]),
+            (Action::DeleteChar, &["Backspace"]),
+            // Alt+Backspace is the macOS word-delete shortcut
+            (Action::DeleteWord, &["Ctrl+Backspace", "Ctrl+W", "Alt+Backspace"]),
+            (Action::DeleteLine, &["Ctrl+Shift+Backspace", "Meta+Backspace"]),
+            (Action::ToggleOverlays, &["Ctrl+Shift+O"]),
+        ];
+        KeyBindings(
+            defaults
+                .iter()
+                .map(|(action, combos)| {
+                    let combos = combos.iter().map(|c| KeyCombo::parse(c).unwrap()).collect();
+                    (*action, combos)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    pub fn action_for(&self, pressed: &KeyCombo) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(_, combos)| combos.iter().any(|c| c.matches(pressed)))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn combos(&self, action: Action) -> &[KeyCombo] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // A combo can only do one thing, so one that another action already uses is refused
+    // rather than left to whichever action `action_for` happens to find first.
+    pub fn set(&mut self, action: Action, combos: Vec<KeyCombo>) -> Result<(), String> {
+        for combo in &combos {
+            let taken = self.0.iter().find(|(other, bound)| **other != action && bound.iter().any(|c| c.matches(combo)));
+            if let Some((other, _)) = taken {
+                return Err(format!("'{}' is already bound to {}", combo, other.label()));
+            }
+        }
+        self.0.insert(action, combos);
+        Ok(())
+    }
+
+    // Comma-separated list used for display and editing, e.g. "Ctrl+W, Alt+Backspace".
+    pub fn describe(&self, action: Action) -> String {
+        self.combos(action)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn parse_list(s: &str) -> Result<Vec<KeyCombo>, String> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(KeyCombo::parse)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(s: &str) -> KeyCombo {
+        KeyCombo::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for s in ["Tab", "Ctrl+Shift+Backspace", "Alt+Backspace", "Ctrl+W", "Space", "Ctrl++"] {
+            assert_eq!(press(s).to_string(), s);
+        }
+        assert_eq!(press("esc").key, "Escape");
+        assert_eq!(press("cmd+backspace").to_string(), "Meta+backspace");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(KeyCombo::parse("").is_err());
+        assert!(KeyCombo::parse("Ctrl+").is_err());
+        assert!(KeyCombo::parse("Hyper+A").is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.action_for(&press("Tab")), Some(Action::NewQuote));
+        assert_eq!(bindings.action_for(&press("Escape")), Some(Action::Finish));
+        assert_eq!(bindings.action_for(&press("Backspace")), Some(Action::DeleteChar));
+        assert_eq!(bindings.action_for(&press("Ctrl+Backspace")), Some(Action::DeleteWord));
+        assert_eq!(bindings.action_for(&press("Ctrl+w")), Some(Action::DeleteWord));
+        assert_eq!(bindings.action_for(&press("Alt+Backspace")), Some(Action::DeleteWord));
+        assert_eq!(bindings.action_for(&press("Ctrl+Shift+Backspace")), Some(Action::DeleteLine));
+        assert_eq!(bindings.action_for(&press("a")), None);
+        assert_eq!(bindings.action_for(&press("Shift+Tab")), None);
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let mut bindings = KeyBindings::default();
+        bindings.set(Action::NewQuote, KeyBindings::parse_list("Shift+Tab, F2").unwrap()).unwrap();
+        assert_eq!(bindings.action_for(&press("Tab")), None);
+        assert_eq!(bindings.action_for(&press("F2")), Some(Action::NewQuote));
+        assert_eq!(bindings.describe(Action::NewQuote), "Shift+Tab, F2");
+    }
+
+    #[test]
+    fn test_conflicting_combos_are_refused() {
+        let mut bindings = KeyBindings::default();
+        let err = bindings.set(Action::Restart, vec![press("tab")]).unwrap_err();
+        assert_eq!(err, "'tab' is already bound to New quote");
+        assert_eq!(bindings.action_for(&press("Tab")), Some(Action::NewQuote));
+        assert_eq!(bindings.describe(Action::Restart), "Ctrl+Enter");
+
+        // Rebinding an action to its own combos, or to one freed up first, is fine
+        bindings.set(Action::NewQuote, vec![press("Tab"), press("F2")]).unwrap();
+        bindings.set(Action::NewQuote, vec![press("F2")]).unwrap();
+        bindings.set(Action::Restart, vec![press("Tab")]).unwrap();
+        assert_eq!(bindings.action_for(&press("Tab")), Some(Action::Restart));
+    }
+
+    #[test]
+    fn test_non_ascii_keys_type_characters() {
+        use std::path::Path;
+        use super::super::quotes::{pack_source, Pack};
+
+        let source = pack_source("german").unwrap();
+        let raw = source.read(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let pack = Pack::parse(source, &raw).unwrap();
+        let quote = pack.quotes.iter().find(|q| !q.text.is_ascii()).expect("a quote with umlauts");
+        let typed: String = quote.text.chars().map(String::from).filter(|key| types_char(key)).collect();
+        assert_eq!(typed, quote.text);
+
+        for key in ["ä", "ß", "ñ", "¿", "¡", "é"] {
+            assert!(types_char(key), "{}", key);
+        }
+        for key in ["Shift", "Dead", "Enter", ""] {
+            assert!(!types_char(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_stored_bindings() {
+        // Unbinding Tab survives being saved and loaded
+        let mut bindings = KeyBindings::default();
+        bindings.set(Action::NewQuote, KeyBindings::parse_list("").unwrap()).unwrap();
+        let stored = serde_json::to_string(&bindings).unwrap();
+        let loaded: KeyBindings = serde_json::from_str(&stored).unwrap();
+        assert_eq!(loaded, bindings);
+        assert_eq!(loaded.action_for(&press("Tab")), None);
+
+        // Actions that weren't stored get their defaults
+        let loaded: KeyBindings = serde_json::from_str(r#"{"restart": ["F5"]}"#).unwrap();
+        assert_eq!(loaded.action_for(&press("F5")), Some(Action::Restart));
+        assert_eq!(loaded.action_for(&press("Ctrl+Enter")), None);
+        assert_eq!(loaded.action_for(&press("Tab")), Some(Action::NewQuote));
+    }
+}
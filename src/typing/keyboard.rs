@@ -0,0 +1,401 @@
+// An on-screen keyboard that shows the next key and the finger to press it with.
+//
+// Keys are laid out from two independent choices: the character layout (QWERTY, Dvorak,
+// Colemak), which says what each key types, and the physical layout (ANSI or ISO), which
+// says where the keys are. Fingers follow the physical keys, as in touch typing, so the
+// same finger presses the same key whatever it types.
+
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use super::theme::{use_theme, with_alpha};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 3] = [Layout::Qwerty, Layout::Dvorak, Layout::Colemak];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Layout::Qwerty => "QWERTY",
+            Layout::Dvorak => "Dvorak",
+            Layout::Colemak => "Colemak",
+        }
+    }
+
+    // The characters of the four character rows, unshifted and shifted, as on a US ANSI
+    // keyboard. The number and top rows end with the keys right of P, including `\`.
+    fn rows(&self) -> [(&'static str, &'static str); 4] {
+        match self {
+            Layout::Qwerty => [
+                ("`1234567890-=", "~!@#$%^&*()_+"),
+                ("qwertyuiop[]\\", "QWERTYUIOP{}|"),
+                ("asdfghjkl;'", "ASDFGHJKL:\""),
+                ("zxcvbnm,./", "ZXCVBNM<>?"),
+            ],
+            Layout::Dvorak => [
+                ("`1234567890[]", "~!@#$%^&*(){}"),
+                ("',.pyfgcrl/=\\", "\"<>PYFGCRL?+|"),
+                ("aoeuidhtns-", "AOEUIDHTNS_"),
+                (";qjkxbmwvz", ":QJKXBMWVZ"),
+            ],
+            Layout::Colemak => [
+                ("`1234567890-=", "~!@#$%^&*()_+"),
+                ("qwfpgjluy;[]\\", "QWFPGJLUY:{}|"),
+                ("arstdhneio'", "ARSTDHNEIO\""),
+                ("zxcvbkm,./", "ZXCVBKM<>?"),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhysicalLayout {
+    #[default]
+    Ansi,
+    // A tall Enter key, with the key above it moved next to Enter and an extra key
+    // left of the bottom row
+    Iso,
+}
+
+impl PhysicalLayout {
+    pub const ALL: [PhysicalLayout; 2] = [PhysicalLayout::Ansi, PhysicalLayout::Iso];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PhysicalLayout::Ansi => "ANSI",
+            PhysicalLayout::Iso => "ISO",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyboardOptions {
+    pub enabled: bool,
+    pub layout: Layout,
+    pub physical: PhysicalLayout,
+}
+
+impl Default for KeyboardOptions {
+    fn default() -> Self {
+        KeyboardOptions { enabled: false, layout: Layout::Qwerty, physical: PhysicalLayout::Ansi }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Finger {
+    LeftPinky,
+    LeftRing,
+    LeftMiddle,
+    LeftIndex,
+    Thumb,
+    RightIndex,
+    RightMiddle,
+    RightRing,
+    RightPinky,
+}
+
+impl Finger {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Finger::LeftPinky => "Left pinky",
+            Finger::LeftRing => "Left ring finger",
+            Finger::LeftMiddle => "Left middle finger",
+            Finger::LeftIndex => "Left index finger",
+            Finger::Thumb => "Thumb",
+            Finger::RightIndex => "Right index finger",
+            Finger::RightMiddle => "Right middle finger",
+            Finger::RightRing => "Right ring finger",
+            Finger::RightPinky => "Right pinky",
+        }
+    }
+
+    pub fn is_left(&self) -> bool {
+        matches!(self, Finger::LeftPinky | Finger::LeftRing | Finger::LeftMiddle | Finger::LeftIndex)
+    }
+}
+
+use Finger::*;
+
+// Fingers for the character keys of each row, from the left. The number row starts one
+// key further left than the rows below it.
+const NUMBER_ROW_FINGERS: [Finger; 13] = [
+    LeftPinky, LeftPinky, LeftRing, LeftMiddle, LeftIndex, LeftIndex, RightIndex, RightIndex, RightMiddle, RightRing,
+    RightPinky, RightPinky, RightPinky,
+];
+const LETTER_ROW_FINGERS: [Finger; 13] = [
+    LeftPinky, LeftRing, LeftMiddle, LeftIndex, LeftIndex, RightIndex, RightIndex, RightMiddle, RightRing, RightPinky,
+    RightPinky, RightPinky, RightPinky,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    // Unshifted and shifted character
+    Char(char, char),
+    Shift,
+    // Keys that don't type anything in the text, e.g. Tab
+    Other(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Key {
+    pub kind: KeyKind,
+    pub finger: Finger,
+    // In multiples of a letter key
+    pub width: f32,
+}
+
+impl Key {
+    fn char(base: char, shifted: char, finger: Finger) -> Self {
+        Key { kind: KeyKind::Char(base, shifted), finger, width: 1.0 }
+    }
+
+    fn other(label: &'static str, finger: Finger, width: f32) -> Self {
+        Key { kind: KeyKind::Other(label), finger, width }
+    }
+
+    pub fn label(&self) -> String {
+        match self.kind {
+            KeyKind::Char(' ', _) => String::new(),
+            // Letters show the capital, like the keycaps
+            KeyKind::Char(base, shifted) if base.is_alphabetic() => shifted.to_string(),
+            KeyKind::Char(base, shifted) => format!("{}{}", shifted, base),
+            KeyKind::Shift => "Shift".to_string(),
+            KeyKind::Other(label) => label.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyPos {
+    pub row: usize,
+    pub col: usize,
+}
+
+fn char_keys(chars: (&str, &str), fingers: &[Finger]) -> Vec<Key> {
+    chars.0.chars().zip(chars.1.chars()).zip(fingers).map(|((base, shifted), &finger)| Key::char(base, shifted, finger)).collect()
+}
+
+// Every row is 15 keys wide
+pub fn rows(layout: Layout, physical: PhysicalLayout) -> Vec<Vec<Key>> {
+    let [number, top, home, bottom] = layout.rows();
+    let mut number_row = char_keys(number, &NUMBER_ROW_FINGERS);
+    number_row.push(Key::other("Backspace", RightPinky, 2.0));
+
+    let mut top_row = vec![Key::other("Tab", LeftPinky, 1.5)];
+    top_row.extend(char_keys(top, &LETTER_ROW_FINGERS));
+    let mut home_row = vec![Key::other("Caps", LeftPinky, 1.75)];
+    home_row.extend(char_keys(home, &LETTER_ROW_FINGERS));
+    let mut bottom_row = vec![Key { kind: KeyKind::Shift, finger: LeftPinky, width: 2.25 }];
+
+    match physical {
+        PhysicalLayout::Ansi => {
+            if let Some(last) = top_row.last_mut() {
+                last.width = 1.5;
+            }
+            home_row.push(Key::other("Enter", RightPinky, 2.25));
+        }
+        PhysicalLayout::Iso => {
+            // The top of the tall Enter key takes the place of the key above it
+            if let Some(moved) = top_row.pop() {
+                home_row.push(moved);
+            }
+            top_row.push(Key::other("Enter", RightPinky, 1.5));
+            home_row.push(Key::other("", RightPinky, 1.25));
+            bottom_row[0].width = 1.25;
+            // The extra key types the same as the one moved next to Enter
+            if let Some(&Key { kind: KeyKind::Char(base, shifted), .. }) = home_row.get(home_row.len() - 2) {
+                bottom_row.push(Key::char(base, shifted, LeftPinky));
+            }
+        }
+    }
+    bottom_row.extend(char_keys(bottom, &LETTER_ROW_FINGERS));
+    bottom_row.push(Key { kind: KeyKind::Shift, finger: RightPinky, width: 2.75 });
+
+    let space_row = vec![
+        Key::other("", LeftPinky, 4.0),
+        Key { kind: KeyKind::Char(' ', ' '), finger: Thumb, width: 6.25 },
+        Key::other("", RightPinky, 4.75),
+    ];
+    vec![number_row, top_row, home_row, bottom_row, space_row]
+}
+
+// The first key that types `ch`, and whether it needs shift
+pub fn find(rows: &[Vec<Key>], ch: char) -> Option<(KeyPos, bool)> {
+    rows.iter().enumerate().find_map(|(row, keys)| {
+        keys.iter().enumerate().find_map(|(col, key)| match key.kind {
+            KeyKind::Char(base, _) if base == ch => Some((KeyPos { row, col }, false)),
+            KeyKind::Char(_, shifted) if shifted == ch => Some((KeyPos { row, col }, true)),
+            _ => None,
+        })
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Guide {
+    pub key: KeyPos,
+    pub finger: Finger,
+    // Held with the other hand
+    pub shift: Option<KeyPos>,
+}
+
+// How to type `ch`, None if the layout has no key for it
+pub fn guide(rows: &[Vec<Key>], ch: char) -> Option<Guide> {
+    let (key, shifted) = find(rows, ch)?;
+    let finger = rows[key.row][key.col].finger;
+    let shift = shifted
+        .then(|| {
+            let mut shifts = rows.iter().enumerate().flat_map(|(row, keys)| {
+                keys.iter().enumerate().filter(|(_, k)| k.kind == KeyKind::Shift).map(move |(col, _)| KeyPos { row, col })
+            });
+            if finger.is_left() {
+                shifts.last()
+            } else {
+                shifts.next()
+            }
+        })
+        .flatten();
+    Some(Guide { key, finger, shift })
+}
+
+// Size of a letter key
+const KEY_REM: f32 = 2.5;
+
+#[derive(Properties, PartialEq)]
+pub struct OnScreenKeyboardProps {
+    pub options: KeyboardOptions,
+    // The next character of the text
+    pub next: Option<char>,
+    // The character just typed, if it was a mistake
+    #[prop_or_default]
+    pub mistyped: Option<char>,
+    // Changes with every keystroke, so the same mistake flashes again
+    #[prop_or_default]
+    pub keystroke: usize,
+}
+
+#[function_component]
+pub fn OnScreenKeyboard(props: &OnScreenKeyboardProps) -> Html {
+    let theme = use_theme();
+    let options = props.options;
+    let rows = use_memo((options.layout, options.physical), |(layout, physical)| rows(*layout, *physical));
+    let next = props.next.and_then(|ch| guide(&rows, ch));
+    let mistyped = props.mistyped.and_then(|ch| find(&rows, ch)).map(|(pos, _)| pos);
+
+    html! {
+        <div class="mt-6 flex flex-col items-center gap-1 font-mono text-sm select-none" aria-hidden="true">
+            { for rows.iter().enumerate().map(|(row, keys)| html! {
+                <div class="flex gap-1">
+                    { for keys.iter().enumerate().map(|(col, key)| {
+                        let pos = KeyPos { row, col };
+                        let is_next = next.is_some_and(|g| g.key == pos || g.shift == Some(pos));
+                        let same_finger = next.is_some_and(|g| g.finger == key.finger && key.kind != KeyKind::Shift);
+                        let style = if is_next {
+                            format!("background-color: {}; color: {};", theme.caret, theme.background)
+                        } else if same_finger {
+                            format!("background-color: {}; color: {};", with_alpha(&theme.accent, 0.25), theme.correct)
+                        } else {
+                            format!("background-color: {}; color: {};", with_alpha(&theme.pending, 0.15), theme.pending)
+                        };
+                        let size = format!("width: {}rem; height: {}rem;", key.width * KEY_REM - 0.25, KEY_REM);
+                        html! {
+                            <div class="relative flex items-center justify-center rounded overflow-hidden" style={format!("{} {}", size, style)}>
+                                {key.label()}
+                                if mistyped == Some(pos) {
+                                    <div key={props.keystroke} class="absolute inset-0 animate-flash"
+                                         style={format!("background-color: {};", with_alpha(&theme.error, 0.6))}></div>
+                                }
+                            </div>
+                        }
+                    }) }
+                </div>
+            }) }
+            <div class="mt-2 h-5" style={format!("color: {};", theme.pending)}>
+                if let Some(guide) = next {
+                    {match guide.shift {
+                        Some(_) if guide.finger.is_left() => format!("{}, right shift", guide.finger.label()),
+                        Some(_) => format!("{}, left shift", guide.finger.label()),
+                        None => guide.finger.label().to_string(),
+                    }}
+                }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_rows() -> Vec<(Layout, PhysicalLayout, Vec<Vec<Key>>)> {
+        Layout::ALL
+            .iter()
+            .flat_map(|&layout| PhysicalLayout::ALL.iter().map(move |&physical| (layout, physical, rows(layout, physical))))
+            .collect()
+    }
+
+    #[test]
+    fn test_rows_are_the_same_width() {
+        for (layout, physical, rows) in all_rows() {
+            for keys in &rows {
+                let width: f32 = keys.iter().map(|k| k.width).sum();
+                assert!((width - 15.0).abs() < 1e-6, "{:?} {:?}: {:?}", layout, physical, keys);
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_printable_character_has_a_key() {
+        for (layout, physical, rows) in all_rows() {
+            for ch in (' '..='~').filter(|c| !c.is_control()) {
+                assert!(find(&rows, ch).is_some(), "{:?} {:?} has no key for {:?}", layout, physical, ch);
+            }
+            assert_eq!(find(&rows, '\u{e9}'), None);
+        }
+    }
+
+    #[test]
+    fn test_home_row_fingers() {
+        let home = |layout: Layout, ch: char| guide(&rows(layout, PhysicalLayout::Ansi), ch).map(|g| (g.key.row, g.finger));
+        assert_eq!(home(Layout::Qwerty, 'f'), Some((2, LeftIndex)));
+        assert_eq!(home(Layout::Qwerty, 'j'), Some((2, RightIndex)));
+        assert_eq!(home(Layout::Dvorak, 'u'), Some((2, LeftIndex)));
+        assert_eq!(home(Layout::Dvorak, 's'), Some((2, RightPinky)));
+        assert_eq!(home(Layout::Colemak, 't'), Some((2, LeftIndex)));
+        assert_eq!(home(Layout::Colemak, 'o'), Some((2, RightPinky)));
+        assert_eq!(home(Layout::Qwerty, ' '), Some((4, Thumb)));
+        assert_eq!(home(Layout::Qwerty, '1'), Some((0, LeftPinky)));
+    }
+
+    #[test]
+    fn test_shift_uses_the_other_hand() {
+        let rows = rows(Layout::Qwerty, PhysicalLayout::Ansi);
+        let capital_j = guide(&rows, 'J').unwrap();
+        assert_eq!(capital_j.shift, Some(KeyPos { row: 3, col: 0 }));
+        let capital_a = guide(&rows, 'A').unwrap();
+        assert_eq!(capital_a.shift, Some(KeyPos { row: 3, col: rows[3].len() - 1 }));
+        assert_eq!(guide(&rows, 'a').unwrap().shift, None);
+        assert_eq!(guide(&rows, 'a').unwrap().key, capital_a.key);
+    }
+
+    #[test]
+    fn test_iso_moves_the_backslash_key() {
+        let ansi = rows(Layout::Qwerty, PhysicalLayout::Ansi);
+        let iso = rows(Layout::Qwerty, PhysicalLayout::Iso);
+        assert_eq!(find(&ansi, '\\').map(|(p, _)| p.row), Some(1));
+        assert_eq!(find(&iso, '\\').map(|(p, _)| p.row), Some(2));
+        assert_eq!(iso[3].len(), ansi[3].len() + 1);
+        // Letters stay where they are
+        assert_eq!(find(&iso, 'z').map(|(p, _)| p.row), Some(3));
+        assert_eq!(guide(&iso, 'z').unwrap().finger, guide(&ansi, 'z').unwrap().finger);
+    }
+}
@@ -0,0 +1,158 @@
+// Wrapping the text into lines that fit the typing view.
+//
+// The view measures its own width and the width of one character of its font, so the
+// number of characters per line follows the screen. Quotes are split on single spaces,
+// and every word but the last on a line is followed by one, so positions in a line
+// map one to one onto characters of the quote.
+//
+// In tape mode the whole text is one line that scrolls past a fixed caret instead.
+
+use serde::{Deserialize, Serialize};
+
+// Used until the view has been measured, e.g. when rendering on the server.
+pub const FALLBACK_CHARS_PER_LINE: usize = 55;
+// Below this the text is unreadable anyway, so narrower screens overflow instead.
+const MIN_CHARS_PER_LINE: usize = 10;
+
+pub const MAX_VISIBLE_LINES: usize = 10;
+// Where the caret sits on the tape, as a percentage of the view's width
+pub const TAPE_CARET_PCT: usize = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewOptions {
+    // Lines shown at once, 1 to MAX_VISIBLE_LINES
+    pub lines: usize,
+    pub tape: bool,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        ViewOptions { lines: 3, tape: false }
+    }
+}
+
+impl ViewOptions {
+    pub fn visible_lines(&self) -> usize {
+        if self.tape {
+            1
+        } else {
+            self.lines.clamp(1, MAX_VISIBLE_LINES)
+        }
+    }
+}
+
+// Characters that fit in `width` pixels of a monospace font `char_width` pixels wide
+pub fn chars_per_line(width: f64, char_width: f64) -> usize {
+    if width <= 0.0 || char_width <= 0.0 {
+        return FALLBACK_CHARS_PER_LINE;
+    }
+    ((width / char_width).floor() as usize).max(MIN_CHARS_PER_LINE)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line<'a> {
+    pub words: Vec<&'a str>,
+    // Position of the line's first character in the quote
+    pub start: usize,
+    // Characters in the quote up to the next line, including the space between them
+    pub len: usize,
+}
+
+// Greedy wrap, a word longer than a line gets a line of its own
+pub fn wrap(text: &str, chars_per_line: usize) -> Vec<Line<'_>> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut current = Line { words: Vec::new(), start: 0, len: 0 };
+    for word in text.split_whitespace() {
+        // +1 for the space after it
+        let word_len = word.chars().count() + 1;
+        if current.len + word_len > chars_per_line && !current.words.is_empty() {
+            let start = current.start + current.len;
+            lines.push(std::mem::replace(&mut current, Line { words: Vec::new(), start, len: 0 }));
+        }
+        current.words.push(word);
+        current.len += word_len;
+    }
+    if !current.words.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+// The line holding quote position `pos`. The end of the text counts as the last line.
+pub fn line_of(lines: &[Line], pos: usize) -> usize {
+    lines
+        .iter()
+        .position(|line| pos < line.start + line.len)
+        .unwrap_or(lines.len().saturating_sub(1))
+}
+
+// First line to show so the cursor stays in the middle of the view, or as close to it
+// as the start and end of the text allow
+pub fn first_visible_line(cursor_line: usize, visible: usize, total: usize) -> usize {
+    cursor_line.saturating_sub(visible.saturating_sub(1) / 2).min(total.saturating_sub(visible))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chars_per_line() {
+        assert_eq!(chars_per_line(1000.0, 21.6), 46);
+        assert_eq!(chars_per_line(100.0, 21.6), MIN_CHARS_PER_LINE);
+        assert_eq!(chars_per_line(0.0, 21.6), FALLBACK_CHARS_PER_LINE);
+        assert_eq!(chars_per_line(1000.0, 0.0), FALLBACK_CHARS_PER_LINE);
+    }
+
+    #[test]
+    fn test_wrap() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let lines = wrap(text, 16);
+        let words: Vec<String> = lines.iter().map(|l| l.words.join(" ")).collect();
+        assert_eq!(words, vec!["the quick brown", "fox jumps over", "the lazy dog"]);
+        assert!(words.iter().all(|w| w.chars().count() < 16));
+        assert_eq!(lines[1].start, "the quick brown ".len());
+        assert_eq!(lines.iter().map(|l| l.len).sum::<usize>(), text.len() + 1);
+    }
+
+    #[test]
+    fn test_long_words_get_their_own_line() {
+        let lines = wrap("a extraordinarily b", 5);
+        let words: Vec<Vec<&str>> = lines.iter().map(|l| l.words.clone()).collect();
+        assert_eq!(words, vec![vec!["a"], vec!["extraordinarily"], vec!["b"]]);
+    }
+
+    #[test]
+    fn test_line_of() {
+        let lines = wrap("aaa bbb ccc", 8);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(line_of(&lines, 0), 0);
+        // The space after "bbb" still belongs to the first line
+        assert_eq!(line_of(&lines, 7), 0);
+        assert_eq!(line_of(&lines, 8), 1);
+        assert_eq!(line_of(&lines, 11), 1);
+        assert_eq!(line_of(&[], 3), 0);
+    }
+
+    #[test]
+    fn test_first_visible_line() {
+        // Three lines keep the cursor on the second one
+        assert_eq!(first_visible_line(0, 3, 10), 0);
+        assert_eq!(first_visible_line(1, 3, 10), 0);
+        assert_eq!(first_visible_line(5, 3, 10), 4);
+        // ...until the end of the text
+        assert_eq!(first_visible_line(9, 3, 10), 7);
+        assert_eq!(first_visible_line(5, 1, 10), 5);
+        assert_eq!(first_visible_line(5, 10, 10), 0);
+        assert_eq!(first_visible_line(5, 10, 4), 0);
+    }
+
+    #[test]
+    fn test_visible_lines() {
+        assert_eq!(ViewOptions::default().visible_lines(), 3);
+        assert_eq!(ViewOptions { lines: 0, tape: false }.visible_lines(), 1);
+        assert_eq!(ViewOptions { lines: 50, tape: false }.visible_lines(), MAX_VISIBLE_LINES);
+        assert_eq!(ViewOptions { lines: 5, tape: true }.visible_lines(), 1);
+    }
+}
@@ -97,10 +97,25 @@ pub fn align_incremental(quote: &str, input: &str) -> Vec<(EditOp, Option<char>,
     result
 }
 
+// Characters of the quote typed correctly so far
+pub fn correct_chars(quote: &str, input: &str) -> usize {
+    align_incremental(quote, input)
+        .iter()
+        .filter(|(op, _, _)| *op == EditOp::Match)
+        .count()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_correct_chars() {
+        assert_eq!(correct_chars("hello world", "hello"), 5);
+        assert_eq!(correct_chars("hello world", "hallo w"), 6);
+        assert_eq!(correct_chars("hello", ""), 0);
+    }
+
     #[test]
     fn test_perfect_match() {
         let quote = "hello world";
@@ -0,0 +1,251 @@
+// Speed profile metrics derived from keystroke timestamps.
+// These complement the averaged WPM/CPM with measures of how steady the typing was.
+
+use std::collections::HashSet;
+
+// Width of each histogram bucket, in WPM.
+pub const HISTOGRAM_BUCKET_WPM: f64 = 10.0;
+
+// Intervals faster than this all go in the top bucket. Keys registered a millisecond
+// apart, e.g. from rollover, would otherwise stretch the chart to thousands of WPM.
+pub const HISTOGRAM_MAX_WPM: f64 = 300.0;
+
+// Window used for burst speed, in milliseconds.
+pub const BURST_WINDOW_MS: f64 = 1000.0;
+
+// Intervals longer than this are treated as pauses and excluded from consistency
+// and the histogram, so a single hesitation doesn't dominate the statistics.
+pub const MAX_INTERVAL_MS: f64 = 2000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedProfile {
+    /// 0-100, where 100 means perfectly even inter-key intervals.
+    pub consistency: f64,
+    /// Speed counting every character typed, correct or not, including ones deleted later.
+    pub raw_wpm: f64,
+    /// Peak speed over any `BURST_WINDOW_MS` window, counting correct keystrokes only.
+    pub burst_wpm: f64,
+    /// (bucket lower bound in WPM, number of intervals in the bucket)
+    pub histogram: Vec<(f64, usize)>,
+}
+
+impl SpeedProfile {
+    // `typed_intervals` are the gaps between every character typed, deleted or not, see
+    // `anticheat::InputSignals`. The keystroke times only cover the text that's left.
+    pub fn from_keystrokes(keystroke_times: &[f64], error_positions: &[usize], typed_intervals: &[f64]) -> Self {
+        let intervals = inter_key_intervals(keystroke_times);
+        let consistency = coefficient_of_variation(&intervals)
+            .map(|cv| ((1.0 - cv) * 100.0).clamp(0.0, 100.0))
+            .unwrap_or(0.0);
+
+        SpeedProfile {
+            consistency,
+            raw_wpm: raw_wpm(typed_intervals),
+            burst_wpm: burst_wpm(keystroke_times, error_positions, BURST_WINDOW_MS),
+            histogram: speed_histogram(&intervals, HISTOGRAM_BUCKET_WPM),
+        }
+    }
+}
+
+// (WPM, CPM), counting correctly typed characters only. Shared by the live HUD and
+// the results, so both report the same speed.
+pub fn speed(correct_chars: usize, elapsed_ms: f64) -> (f64, f64) {
+    if elapsed_ms <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let cpm = correct_chars as f64 / (elapsed_ms / 60_000.0);
+    (cpm / 5.0, cpm)
+}
+
+// Percentage of typed characters that weren't mistakes
+pub fn accuracy(error_count: usize, typed_chars: usize) -> f64 {
+    if typed_chars == 0 {
+        return 100.0;
+    }
+    ((1.0 - error_count as f64 / typed_chars as f64) * 100.0).max(0.0)
+}
+
+fn interval_to_wpm(interval_ms: f64) -> f64 {
+    // One keystroke per interval, five keystrokes per word.
+    60_000.0 / interval_ms / 5.0
+}
+
+// Time between consecutive keystrokes, ignoring pauses.
+pub fn inter_key_intervals(keystroke_times: &[f64]) -> Vec<f64> {
+    keystroke_times
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|&d| d > 0.0 && d <= MAX_INTERVAL_MS)
+        .collect()
+}
+
+// Standard deviation divided by the mean. None if there is not enough data.
+pub fn coefficient_of_variation(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if mean <= 0.0 {
+        return None;
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    Some(variance.sqrt() / mean)
+}
+
+// Keystrokes per minute over the intervals between them. The first keystroke starts the
+// clock, so it isn't counted, the same as when the span of the times is used.
+pub fn raw_wpm(intervals: &[f64]) -> f64 {
+    let elapsed_ms: f64 = intervals.iter().sum();
+    if elapsed_ms <= 0.0 {
+        return 0.0;
+    }
+    (intervals.len() as f64 / 5.0) / (elapsed_ms / 60_000.0)
+}
+
+// Highest number of correct keystrokes landing in any window of `window_ms`,
+// converted to WPM. Falls back to the whole run if it is shorter than the window.
+pub fn burst_wpm(keystroke_times: &[f64], error_positions: &[usize], window_ms: f64) -> f64 {
+    if keystroke_times.len() < 2 || window_ms <= 0.0 {
+        return 0.0;
+    }
+
+    let mut best = 0usize;
+    let mut start = 0;
+    let mut correct_in_window = 0usize;
+    let errors: HashSet<usize> = error_positions.iter().copied().collect();
+    let is_correct = |i: usize| !errors.contains(&i);
+
+    for end in 0..keystroke_times.len() {
+        if is_correct(end) {
+            correct_in_window += 1;
+        }
+        while keystroke_times[end] - keystroke_times[start] > window_ms {
+            if is_correct(start) {
+                correct_in_window -= 1;
+            }
+            start += 1;
+        }
+        best = best.max(correct_in_window);
+    }
+
+    let span = keystroke_times[keystroke_times.len() - 1] - keystroke_times[0];
+    let window_min = window_ms.min(span.max(1.0)) / 60_000.0;
+    (best as f64 / 5.0) / window_min
+}
+
+// Buckets the instantaneous speed of each interval.
+// Buckets are contiguous from zero up to the fastest one so the chart has no gaps,
+// and the last one holds everything from `HISTOGRAM_MAX_WPM` up.
+pub fn speed_histogram(intervals: &[f64], bucket_wpm: f64) -> Vec<(f64, usize)> {
+    if intervals.is_empty() || bucket_wpm <= 0.0 {
+        return Vec::new();
+    }
+    let top_bucket = (HISTOGRAM_MAX_WPM / bucket_wpm) as usize;
+    let mut counts: Vec<usize> = Vec::new();
+    for &interval in intervals {
+        let bucket = ((interval_to_wpm(interval) / bucket_wpm) as usize).min(top_bucket);
+        if bucket >= counts.len() {
+            counts.resize(bucket + 1, 0);
+        }
+        counts[bucket] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (i as f64 * bucket_wpm, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evenly_spaced(n: usize, step: f64) -> Vec<f64> {
+        (0..n).map(|i| 1000.0 + i as f64 * step).collect()
+    }
+
+    #[test]
+    fn test_speed_and_accuracy() {
+        // 50 characters in 12 seconds = 250 CPM = 50 WPM
+        assert_eq!(speed(50, 12_000.0), (50.0, 250.0));
+        assert_eq!(speed(50, 0.0), (0.0, 0.0));
+        assert_eq!(accuracy(0, 0), 100.0);
+        assert_eq!(accuracy(5, 20), 75.0);
+        assert_eq!(accuracy(30, 20), 0.0);
+    }
+
+    #[test]
+    fn test_steady_typing_is_fully_consistent() {
+        let times = evenly_spaced(50, 150.0);
+        let profile = SpeedProfile::from_keystrokes(&times, &[], &inter_key_intervals(&times));
+        assert!((profile.consistency - 100.0).abs() < 1e-9);
+        // 150ms per key = 400 CPM = 80 WPM
+        assert!((profile.raw_wpm - 80.0).abs() < 1e-9);
+        assert_eq!(profile.histogram.last(), Some(&(80.0, 49)));
+    }
+
+    #[test]
+    fn test_bursty_typing_is_less_consistent() {
+        // Bursts of fast keys separated by hesitations.
+        let mut times = Vec::new();
+        let mut t = 0.0;
+        for _ in 0..5 {
+            for _ in 0..10 {
+                t += 60.0;
+                times.push(t);
+            }
+            t += 600.0;
+        }
+        let steady_times = evenly_spaced(50, 220.0);
+        let bursty = SpeedProfile::from_keystrokes(&times, &[], &inter_key_intervals(&times));
+        let steady = SpeedProfile::from_keystrokes(&steady_times, &[], &inter_key_intervals(&steady_times));
+        assert!(bursty.consistency < steady.consistency);
+        assert!(bursty.burst_wpm > steady.burst_wpm);
+    }
+
+    #[test]
+    fn test_raw_counts_deleted_keystrokes() {
+        // Fifteen characters 150ms apart, of which the middle five were deleted. Only ten
+        // keystroke times are left, but all fourteen gaps count.
+        let kept: Vec<f64> = (0..15).filter(|i| !(5..10).contains(i)).map(|i| i as f64 * 150.0).collect();
+        let typed = vec![150.0; 14];
+        let profile = SpeedProfile::from_keystrokes(&kept, &[], &typed);
+        assert!((profile.raw_wpm - 80.0).abs() < 1e-9);
+        assert!(profile.raw_wpm > raw_wpm(&inter_key_intervals(&kept)));
+        assert_eq!(raw_wpm(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_pauses_are_ignored() {
+        let mut times = evenly_spaced(10, 100.0);
+        times.push(times[9] + 10_000.0);
+        assert_eq!(inter_key_intervals(&times).len(), 9);
+    }
+
+    #[test]
+    fn test_burst_excludes_errors() {
+        let times = evenly_spaced(21, 50.0); // 21 keys in exactly one second
+        let clean = burst_wpm(&times, &[], 1000.0);
+        let with_errors = burst_wpm(&times, &[0, 1, 2, 3, 4], 1000.0);
+        assert!((clean - 21.0 / 5.0 * 60.0).abs() < 1e-9);
+        assert!((with_errors - 16.0 / 5.0 * 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_histogram_is_capped() {
+        // 1ms apart is 12000 WPM, which lands in the top bucket
+        let histogram = speed_histogram(&[1.0, 1.0, 150.0], HISTOGRAM_BUCKET_WPM);
+        assert_eq!(histogram.len(), (HISTOGRAM_MAX_WPM / HISTOGRAM_BUCKET_WPM) as usize + 1);
+        assert_eq!(histogram.last(), Some(&(HISTOGRAM_MAX_WPM, 2)));
+        assert_eq!(histogram[8], (80.0, 1));
+    }
+
+    #[test]
+    fn test_not_enough_data() {
+        assert_eq!(coefficient_of_variation(&[100.0]), None);
+        assert_eq!(raw_wpm(&[0.0]), 0.0);
+        assert_eq!(burst_wpm(&[], &[], 1000.0), 0.0);
+        assert!(speed_histogram(&[], 10.0).is_empty());
+    }
+}
@@ -1,31 +1,105 @@
-use yew::{function_component, html, Html, use_node_ref, use_effect, NodeRef};
+use gloo_events::EventListener;
+use yew::{function_component, html, Callback, Html, MouseEvent, Properties, use_node_ref, use_effect, use_effect_with, use_state, use_state_eq};
 use web_sys::Element;
 
+mod anticheat;
+mod caret;
+mod custom_text;
+mod difficulty;
+pub mod drill;
+mod hud;
+mod keybindings;
+mod keyboard;
+mod layout;
 mod matching;
-mod quotes;
+mod metrics;
+pub mod quotes;
+pub mod selection;
+mod sections;
+mod settings;
+mod settings_panel;
+mod sound;
+mod text_view;
+mod theme;
+mod transform;
+pub mod words;
+#[cfg(test)]
+mod validate;
 
 mod hook;
 mod results;
 
 use matching::{align_incremental, EditOp};
+use settings::use_settings;
+
+// Measured to find the width of one character of the typing font
+const MEASURE_SAMPLE: &str = "0000000000";
+
+pub use custom_text::CustomText;
+pub use hook::TextSource;
+pub use settings::SettingsProvider;
+
+#[derive(Properties, PartialEq)]
+pub struct TypingHomeProps {
+    #[prop_or_default]
+    pub source: hook::TextSource,
+}
 
 #[function_component]
-pub fn TypingHome() -> Html {
-    let game = hook::use_typing_game();
+pub fn TypingHome(props: &TypingHomeProps) -> Html {
+    let game = hook::use_typing_game(props.source.clone());
+    let settings_open = use_state(|| false);
+    let theme = theme::use_theme();
+    let settings = use_settings().settings;
+    let caret = settings.caret;
+    let view = settings.view;
+    let feedback = settings.word_feedback;
+    let policy = settings.display_policy;
+    let keyboard = settings.keyboard;
+    // Live accuracy and error counts would give away what blind mode hides
+    let hud_options = if policy.shows_errors() {
+        settings.hud
+    } else {
+        hud::HudOptions { accuracy: false, errors: false, ..settings.hud }
+    };
     
     // Refs for smooth cursor
-    let active_char_ref = use_node_ref();
     let cursor_ref = use_node_ref();
     let marker_ref = use_node_ref();
 
+    // Lines are as wide as the text area allows, measured again when the window is resized
+    let text_ref = use_node_ref();
+    let measure_ref = use_node_ref();
+    let chars_per_line = use_state_eq(|| layout::FALLBACK_CHARS_PER_LINE);
+    {
+        let text_ref = text_ref.clone();
+        let measure_ref = measure_ref.clone();
+        let chars_per_line = chars_per_line.clone();
+        // The text area is only there while typing, so measure whenever it comes back
+        use_effect_with(game.finished, move |_| {
+            let measure = move || {
+                if let (Some(text), Some(sample)) = (text_ref.cast::<Element>(), measure_ref.cast::<Element>()) {
+                    let char_width = sample.get_bounding_client_rect().width() / MEASURE_SAMPLE.len() as f64;
+                    chars_per_line.set(layout::chars_per_line(text.client_width() as f64, char_width));
+                }
+            };
+            measure();
+            let listener = web_sys::window().map(|window| EventListener::new(&window, "resize", move |_| measure()));
+            move || drop(listener)
+        });
+    }
+
     // Effect to update cursor position
     {
-        let active_char_ref = active_char_ref.clone();
+        let text_ref = text_ref.clone();
         let cursor_ref = cursor_ref.clone();
         let marker_ref = marker_ref.clone();
         use_effect(move || {
+            let active_char = text_ref
+                .cast::<Element>()
+                .and_then(|text| text.query_selector(&format!("[{}]", text_view::CURSOR_ATTR)).ok().flatten());
             if let (Some(active_char_el), Some(cursor_el), Some(marker_el)) = (
-                active_char_ref.cast::<Element>(),
+                active_char,
                 cursor_ref.cast::<Element>(),
                 marker_ref.cast::<Element>(),
             ) {
@@ -34,10 +108,7 @@ pub fn TypingHome() -> Html {
                 
                 let left = char_rect.left() - marker_rect.left();
                 let top = char_rect.top() - marker_rect.top();
-                let height = char_rect.height();
-                
-                // Construct the full style string.
-                let style_str = format!("left: {}px; top: {}px; height: {}px; opacity: 1;", left, top, height);
+                let style_str = caret.position_style(left, top, char_rect.width(), char_rect.height());
                 let _ = cursor_el.set_attribute("style", &style_str);
             } else if let Some(cursor_el) = cursor_ref.cast::<Element>() {
                  // If active ref is missing (e.g. init or glitch), hide cursor.
@@ -56,28 +127,8 @@ pub fn TypingHome() -> Html {
         let current_quote = &game.current_quote;
         let user_input = &game.user_input;
 
-        // Split quote into words and group into lines
-        let words: Vec<&str> = current_quote.split_whitespace().collect();
-        let chars_per_line = 55; // Approximate chars that fit in 70vw at text-4xl
-
-        // Group words into lines based on character count
-        let mut lines: Vec<Vec<&str>> = Vec::new();
-        let mut current_line_words: Vec<&str> = Vec::new();
-        let mut current_line_len = 0;
-
-        for word in &words {
-            let word_len = word.chars().count() + 1; // +1 for space
-            if current_line_len + word_len > chars_per_line && !current_line_words.is_empty() {
-                lines.push(current_line_words);
-                current_line_words = Vec::new();
-                current_line_len = 0;
-            }
-            current_line_words.push(word);
-            current_line_len += word_len;
-        }
-        if !current_line_words.is_empty() {
-            lines.push(current_line_words);
-        }
+        // The tape is a single line as long as the text
+        let lines = layout::wrap(current_quote, if view.tape { usize::MAX } else { *chars_per_line });
 
         // Get alignment to find cursor position
         let alignment = align_incremental(current_quote, user_input);
@@ -85,178 +136,57 @@ pub fn TypingHome() -> Html {
             .filter(|(op, _, _)| *op != EditOp::Insert)
             .count();
 
-        // Find which line the cursor is on
-        let mut char_count = 0;
-        let mut cursor_line = 0;
-        for (i, line_words) in lines.iter().enumerate() {
-            let line_char_count: usize = line_words.iter().map(|w| w.chars().count() + 1).sum();
-            if char_count + line_char_count > consumed_quote_chars {
-                cursor_line = i;
-                break;
+        let states = text_view::char_states(current_quote, &alignment);
+        let words = text_view::word_states(&states);
+        let current_word = text_view::word_containing(&words, consumed_quote_chars);
+        let render_word = |word: &text_view::WordState| {
+            let (start, end) = (word.span.start, word.span.end);
+            let cursor = (start..end).contains(&consumed_quote_chars).then(|| consumed_quote_chars - start);
+            let is_current = current_word.is_some_and(|w| w.span == word.span);
+            html! {
+                <text_view::Word key={start} chars={states[start..end].to_vec()} {cursor}
+                    highlight={feedback.highlight_current && is_current}
+                    underline={feedback.underline_errors && word.has_error && consumed_quote_chars >= end}
+                    dimmed={feedback.dim_other_sentences && current_word.is_some_and(|w| w.sentence != word.sentence)}
+                    {policy} />
             }
-            char_count += line_char_count;
-            cursor_line = i + 1;
-        }
-
-        // Scroll offset: only advances forward, keeps cursor on line 2 (middle)
-        let new_scroll = if cursor_line <= 1 { 0 } else { cursor_line - 1 };
-        let current_scroll = game.scroll_offset;
-        
-        // Side effect: update scroll offset if needed. 
-        // Note: calling state setter during render is generally bad practice in React/Yew, 
-        // but here it simulates "derived state with side effect". 
-        // A better approach would be to calculate visible lines based on cursor position directly without stored state,
-        // but the original code used stored state to prevent scrolling backward or jumping?
-        // Original code: "Scroll offset: only advances forward".
-        // So we need to respect the stored state.
-        
-        let effective_scroll = if new_scroll > current_scroll {
-            game.set_scroll_offset.emit(new_scroll);
-            new_scroll
-        } else {
-            current_scroll
         };
-        
-        let scroll = effective_scroll;
-
-        // Get the 3 lines to display
-        let visible_lines: Vec<&Vec<&str>> = lines.iter().skip(scroll).take(3).collect();
-
-        // Build the text for visible lines with alignment coloring
-        let mut visible_start_char = 0;
-        for line_words in lines.iter().take(scroll) {
-            visible_start_char += line_words.iter().map(|w| w.chars().count() + 1).sum::<usize>();
-        }
-
-        let mut insertions_before: std::collections::HashMap<usize, Vec<char>> = std::collections::HashMap::new();
-        let mut char_status: std::collections::HashMap<usize, (bool, Option<char>)> = std::collections::HashMap::new();
-
-        let mut quote_pos = 0;
-        for (op, _, input_char) in alignment.iter() {
-            match op {
-                EditOp::Insert => {
-                    insertions_before.entry(quote_pos).or_insert_with(Vec::new).push(input_char.unwrap());
-                }
-                EditOp::Match => {
-                    char_status.insert(quote_pos, (false, None));
-                    quote_pos += 1;
-                }
-                EditOp::Substitute => {
-                    char_status.insert(quote_pos, (true, *input_char));
-                    quote_pos += 1;
-                }
-            }
-        }
-
-        let mut rendered_lines: Vec<Html> = Vec::new();
-        let mut pos = visible_start_char;
-
-        for line_words in visible_lines.iter() {
-            let mut line_elements: Vec<Html> = Vec::new();
-
-            for (word_idx, word) in line_words.iter().enumerate() {
-                for ch in word.chars() {
-                    let inserts_html = if let Some(inserts) = insertions_before.get(&pos) {
-                        html! {
-                            <span class="absolute left-0 flex flex-col-reverse items-center pointer-events-none z-20" style="bottom: 100%; line-height: 1;">
-                                { for inserts.iter().map(|&ins_char| {
-                                    let display_char = if ins_char == ' ' { '\u{2423}' } else { ins_char };
-                                    html! { <span class="text-red-500 dark:text-red-400 text-sm font-bold">{display_char}</span> }
-                                }) }
-                            </span>
-                        }
-                    } else {
-                        html! {}
-                    };
 
-                    let (is_error, typed_char) = char_status.get(&pos).cloned().unwrap_or((false, None));
-                    let show_cursor = pos == consumed_quote_chars;
-                    
-                    // Attach active_char_ref if this is the cursor position
-                    let node_ref = if show_cursor { active_char_ref.clone() } else { NodeRef::default() };
-
-                    let class = if pos < consumed_quote_chars {
-                        if is_error {
-                            "text-red-500 dark:text-red-400 bg-red-900/30"
-                        } else {
-                            "text-white dark:text-white"
-                        }
-                    } else {
-                        "text-gray-500 dark:text-gray-500"
-                    };
-
-                    line_elements.push(html! {
-                        <span class="relative inline">
-                            {inserts_html}
-                            <span ref={node_ref} class={class}>{ch}</span>
-                            {if let Some(typed) = typed_char {
-                                html! { <span class="absolute text-xs text-red-300" style="top: 100%; left: 0; line-height: 1;">{typed}</span> }
-                            } else {
-                                html! {}
-                            }}
-                        </span>
-                    });
-                    pos += 1;
-                }
-                
-                if word_idx < line_words.len() - 1 {
-                    let inserts_html = if let Some(inserts) = insertions_before.get(&pos) {
-                        html! {
-                            <span class="absolute left-0 flex flex-col-reverse items-center pointer-events-none z-20" style="bottom: 100%; line-height: 1;">
-                                { for inserts.iter().map(|&ins_char| {
-                                    let display_char = if ins_char == ' ' { '\u{2423}' } else { ins_char };
-                                    html! { <span class="text-red-500 dark:text-red-400 text-sm font-bold">{display_char}</span> }
-                                }) }
-                            </span>
-                        }
-                    } else {
-                        html! {}
-                    };
-
-                    let (is_error, typed_char) = char_status.get(&pos).cloned().unwrap_or((false, None));
-                    let show_cursor = pos == consumed_quote_chars;
-                    let node_ref = if show_cursor { active_char_ref.clone() } else { NodeRef::default() };
-
-                    let class = if pos < consumed_quote_chars {
-                        if is_error {
-                            "text-red-500 dark:text-red-400 bg-red-900/30"
-                        } else {
-                            "text-white dark:text-white"
-                        }
-                    } else {
-                        "text-gray-500 dark:text-gray-500"
-                    };
-
-                    line_elements.push(html! {
-                        <span class="relative inline">
-                            {inserts_html}
-                            <span ref={node_ref} class={class}>{"\u{00A0}"}</span>
-                            {if let Some(typed) = typed_char {
-                                html! { <span class="absolute text-xs text-red-300" style="top: 100%; left: 0; line-height: 1;">{typed}</span> }
-                            } else {
-                                html! {}
-                            }}
-                        </span>
-                    });
-                    pos += 1;
-                }
+        let rendered_text = if view.tape {
+            // Only the words near the cursor, shifted so the cursor stays where the caret is
+            let reach = *chars_per_line;
+            let window = text_view::words_in(&words, consumed_quote_chars.saturating_sub(reach)..consumed_quote_chars + reach);
+            let behind = consumed_quote_chars - window.first().map_or(consumed_quote_chars, |w| w.span.start);
+            html! {
+                <div class="whitespace-nowrap relative" style={format!("left: calc({}% - {}ch);", layout::TAPE_CARET_PCT, behind)}>
+                    { for window.iter().map(render_word) }
+                </div>
             }
-            if pos < current_quote.chars().count() {
-                // Handle cursor at end of line (split by wrapping)
-                if pos == consumed_quote_chars {
-                    line_elements.push(html! { <span ref={active_char_ref.clone()} class="inline-block w-0 h-8 align-middle"></span> });
+        } else {
+            // Keep the cursor in the middle, derived from the cursor so that nothing
+            // is written back while rendering
+            let visible = view.visible_lines();
+            let cursor_line = layout::line_of(&lines, consumed_quote_chars);
+            let first = layout::first_visible_line(cursor_line, visible, lines.len());
+            lines.iter().skip(first).take(visible).map(|line| {
+                html! {
+                    <div key={line.start} class="whitespace-nowrap">
+                        { for text_view::words_in(&words, line.start..line.start + line.len).iter().map(render_word) }
+                    </div>
                 }
-                pos += 1; 
-            }
+            }).collect::<Html>()
+        };
 
-            rendered_lines.push(html! {
-                <div class="whitespace-nowrap">
-                    {line_elements.into_iter().collect::<Html>()}
-                </div>
-            });
-        }
+        let quote_label = match (&game.quote.section, game.day, game.seed) {
+            (Some(section), _, _) => section.label(),
+            (_, Some(day), _) => format!("Quote of the day, {}", selection::date_label(day)),
+            (_, _, Some(seed)) => format!("{} \u{00b7} seed {}", game.quote.difficulty_class.label(), seed),
+            _ => game.quote.difficulty_class.label().to_string(),
+        };
 
-        let rendered_text = rendered_lines.into_iter().collect::<Html>();
+        let next_char = current_quote.chars().nth(consumed_quote_chars);
+        // Flashing mistyped keys would give away what blind mode hides
+        let mistyped = game.mistyped_key.filter(|_| policy.shows_errors());
 
         let total_chars = current_quote.chars().count();
         let progress_pct = if total_chars > 0 {
@@ -267,29 +197,80 @@ pub fn TypingHome() -> Html {
 
         html! {
             <>
-            <div class="w-full h-1.5 bg-gray-200 rounded-full mb-6 dark:bg-gray-700">
-                <div class="h-1.5 bg-blue-500 rounded-full dark:bg-blue-400 transition-all duration-200 ease-out" style={format!("width: {:.1}%", progress_pct)}></div>
-            </div>
-            <div class="p-6 bg-gray-100 dark:bg-gray-800 rounded-lg relative">
+            if game.show_overlays {
+                <div class="w-full h-1.5 bg-gray-200 rounded-full mb-6 dark:bg-gray-700">
+                    <div class="h-1.5 rounded-full transition-all duration-200 ease-out" style={format!("width: {:.1}%; background-color: {};", progress_pct, theme.accent)}></div>
+                </div>
+            }
+            <div class="p-6 rounded-lg relative" style={format!("background-color: {};", theme.background)}>
                  // Position Marker
                  <div ref={marker_ref} class="absolute top-0 left-0 w-0 h-0 pointer-events-none"></div>
                  // Smooth Cursor
-                 <div ref={cursor_ref} class="absolute w-0.5 bg-yellow-400 transition-all duration-100 ease-out z-10 pointer-events-none" 
-                      style="left: 0; top: 0; height: 1.5em; opacity: 1;"></div>
+                 <div ref={cursor_ref} class="absolute z-10 pointer-events-none" style="left: 0; top: 0; height: 1.5em; opacity: 0;">
+                     // Keyed by the input so the blink restarts on every keystroke
+                     <div key={user_input.len()} class={caret.shape_class()} style={caret.shape_style(&theme.caret)}></div>
+                 </div>
                 
-                <div class="text-4xl font-mono select-none relative z-0" style="line-height: 1.8;">
+                if hud_options.enabled && game.show_overlays {
+                    <hud::StatsHud options={hud_options} input={hud::HudInput {
+                        quote: current_quote.clone(),
+                        input: user_input.clone(),
+                        start_time: game.start_time,
+                        paused_at: game.paused_at,
+                        error_count: game.error_count,
+                        typed_chars: game.total_typed_chars,
+                        time_limit_ms: game.time_limit_ms,
+                    }} />
+                }
+                <div ref={text_ref.clone()} class="text-4xl font-mono select-none relative z-0"
+                     style={if view.tape { "line-height: 1.8; overflow-x: clip;" } else { "line-height: 1.8;" }}>
+                    <span ref={measure_ref.clone()} class="absolute invisible whitespace-pre pointer-events-none" aria-hidden="true">{MEASURE_SAMPLE}</span>
                     {rendered_text}
                 </div>
+
+                <div class="mt-4 flex justify-between text-sm" style={format!("color: {};", theme.pending)}>
+                    <span>{quote_label}</span>
+                    if let Some(attribution) = game.quote.attribution() {
+                        <span>{format!("\u{2014} {}", attribution)}</span>
+                    }
+                </div>
+
+                if game.paused {
+                    <div class="absolute inset-0 flex items-center justify-center rounded-lg z-30 text-2xl font-bold"
+                         style={format!("background-color: {}; color: {};", theme::with_alpha(&theme.background, 0.8), theme.correct)}>
+                        {"Paused"}
+                    </div>
+                }
             </div>
+            if keyboard.enabled {
+                <keyboard::OnScreenKeyboard options={keyboard} next={next_char} {mistyped} keystroke={game.total_typed_chars} />
+            }
             </>
         }
     } else {
         html! {}
     };
 
+    let on_toggle_settings = {
+        let settings_open = settings_open.clone();
+        Callback::from(move |_: MouseEvent| settings_open.set(!*settings_open))
+    };
+
     html! {
-        <div ref={game.div_ref} class="w-full px-4 focus:outline-none" tabindex="0" onkeydown={game.on_keydown} style="max-width: 70vw; margin: 0 auto;">
-            <h2 class="text-3xl font-bold mb-4 text-center">{"ThockFlow"}</h2>
+        <>
+        <div ref={game.div_ref} class="w-full px-4 focus:outline-none" tabindex="0" onkeydown={game.on_keydown} onpaste={game.on_paste} ondrop={game.on_drop} style="max-width: 70vw; margin: 0 auto;">
+            <div class="relative">
+                <h2 class="text-3xl font-bold mb-4 text-center">{"ThockFlow"}</h2>
+                <button class="absolute top-0 right-0 text-sm text-gray-500 hover:underline" onclick={on_toggle_settings}>
+                    {if *settings_open { "Close settings" } else { "Settings" }}
+                </button>
+            </div>
+
+            if let Some(id) = &game.missing_quote_id {
+                <div class="mb-4 p-2 text-center text-sm rounded bg-yellow-400/20 text-gray-600 dark:text-gray-300">
+                    {format!("Quote \"{}\" is no longer available, here is another one.", id)}
+                </div>
+            }
 
             if !game.finished {
                 {game_view}
@@ -303,13 +284,28 @@ pub fn TypingHome() -> Html {
                     total_words={game.total_words}
                     error_count={game.error_count}
                     keystroke_times={game.keystroke_times}
+                    typed_intervals={game.typed_intervals}
                     start_time={game.start_time}
                     error_positions={game.error_positions}
                     current_quote={game.current_quote}
                     user_input={game.user_input}
                     key_log={game.key_log}
+                    verdict={game.verdict}
+                    on_drill={game.start_drill}
+                    section={game.quote.section.clone()}
+                    section_results={game.section_results}
+                    on_continue={game.continue_section}
+                    transforms={game.transforms}
+                    reveal_alignment={!policy.shows_errors()}
                 />
             }
         </div>
+        // Kept outside the typing area so editing settings doesn't type into the quote
+        if *settings_open {
+            <div class="w-full px-4" style="max-width: 70vw; margin: 0 auto;">
+                <settings_panel::SettingsPanel pack_id={game.pack_id.clone()} on_select_pack={game.select_pack.clone()} />
+            </div>
+        }
+        </>
     }
 }
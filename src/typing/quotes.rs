@@ -1,13 +1,569 @@
 // Typing practice quotes
-// Loaded from static/quotes.txt
+// Organised in packs: the main English corpus in static/quotes.txt, plus the files in static/packs.
+// The files are read by the server at runtime; the client only knows the pack names and
+// asks the server for quotes, see `QuoteRequest`. Generated word tests and drills come
+// from the server the same way.
+//
+// Each non-empty line is either a plain-text quote, or a JSON object with metadata:
+// {"id": "...", "text": "...", "author": "...", "source": "...", "language": "en", "tags": ["..."]}
+// Only "text" is required. Both kinds of line can be mixed in the same file.
+//
+// Quotes are identified by a hash of their text unless they carry an explicit id,
+// so reordering or adding lines doesn't change what a saved link points to.
 
-use lazy_static::lazy_static;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
 
-const QUOTES_RAW: &str = include_str!("../../static/quotes.txt");
+use serde::{Deserialize, Serialize};
 
-lazy_static! {
-    pub static ref QUOTES: Vec<&'static str> = QUOTES_RAW
-        .lines()
-        .filter(|line| !line.is_empty())
+use super::difficulty::{self, Difficulty};
+use super::sections::{self, Section};
+use super::selection;
+
+pub const DEFAULT_PACK: &str = "english";
+
+const DEFAULT_LANGUAGE: &str = "en";
+
+// Word counts separating the length classes.
+const SHORT_MAX_WORDS: usize = 100;
+const MEDIUM_MAX_WORDS: usize = 140;
+
+// Hex digits kept from the content hash. 48 bits is plenty for a few thousand quotes.
+const ID_LEN: usize = 12;
+
+pub struct PackSource {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub language: &'static str,
+    // Non-ASCII characters the corpus validation accepts for this pack
+    pub allowed_non_ascii: &'static str,
+    // Relative to the directory the server runs in
+    pub path: &'static str,
+}
+
+impl PackSource {
+    pub fn read(&self, root: &Path) -> io::Result<String> {
+        fs::read_to_string(root.join(self.path))
+    }
+}
+
+pub const PACK_SOURCES: [PackSource; 5] = [
+    PackSource {
+        id: DEFAULT_PACK,
+        name: "English prose",
+        language: DEFAULT_LANGUAGE,
+        allowed_non_ascii: "",
+        path: "static/quotes.txt",
+    },
+    PackSource {
+        id: "programming",
+        name: "Programming jargon",
+        language: DEFAULT_LANGUAGE,
+        allowed_non_ascii: "",
+        path: "static/packs/programming.txt",
+    },
+    PackSource {
+        id: "speeches",
+        name: "Famous speeches",
+        language: DEFAULT_LANGUAGE,
+        allowed_non_ascii: "",
+        path: "static/packs/speeches.txt",
+    },
+    PackSource {
+        id: "german",
+        name: "Deutsch",
+        language: "de",
+        allowed_non_ascii: "äöüÄÖÜß",
+        path: "static/packs/german.txt",
+    },
+    PackSource {
+        id: "spanish",
+        name: "Español",
+        language: "es",
+        allowed_non_ascii: "áéíóúüñÁÉÍÓÚÜÑ¿¡",
+        path: "static/packs/spanish.txt",
+    },
+];
+
+pub fn pack_source(id: &str) -> Option<&'static PackSource> {
+    PACK_SOURCES.iter().find(|p| p.id == id)
+}
+
+// Unknown or missing pack ids fall back to the default pack.
+pub fn pack_source_or_default(id: Option<&str>) -> &'static PackSource {
+    id.and_then(pack_source)
+        .unwrap_or_else(|| pack_source(DEFAULT_PACK).expect("default pack is registered"))
+}
+
+pub struct Pack {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub language: &'static str,
+    pub quotes: Vec<Quote>,
+}
+
+impl Pack {
+    pub fn parse(source: &'static PackSource, raw: &str) -> Result<Pack, CorpusError> {
+        Ok(Pack {
+            id: source.id,
+            name: source.name,
+            language: source.language,
+            // Long texts are played section by section
+            quotes: sections::split_long(parse_corpus(raw, source.language)?),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthClass {
+    Short,
+    Medium,
+    Long,
+}
+
+impl LengthClass {
+    pub const ALL: [LengthClass; 3] = [LengthClass::Short, LengthClass::Medium, LengthClass::Long];
+
+    pub fn for_text(text: &str) -> Self {
+        match text.split_whitespace().count() {
+            n if n < SHORT_MAX_WORDS => LengthClass::Short,
+            n if n < MEDIUM_MAX_WORDS => LengthClass::Medium,
+            _ => LengthClass::Long,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LengthClass::Short => "Short",
+            LengthClass::Medium => "Medium",
+            LengthClass::Long => "Long",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub id: String,
+    pub text: String,
+    pub source: Option<String>,
+    pub author: Option<String>,
+    pub language: String,
+    pub tags: Vec<String>,
+    pub length_class: LengthClass,
+    pub difficulty: f64,
+    // Relative to the rest of the corpus, see `difficulty::buckets`
+    pub difficulty_class: Difficulty,
+    // Set when the quote is part of a longer text
+    #[serde(default)]
+    pub section: Option<Section>,
+}
+
+impl Quote {
+    pub fn from_text(id: Option<String>, text: &str, language: &str) -> Self {
+        Quote {
+            id: id.unwrap_or_else(|| content_id(text)),
+            text: text.to_string(),
+            source: None,
+            author: None,
+            language: language.to_string(),
+            tags: Vec::new(),
+            length_class: LengthClass::for_text(text),
+            difficulty: difficulty::difficulty(text),
+            difficulty_class: Difficulty::Medium,
+            section: None,
+        }
+    }
+
+    // "Author, Source", or whichever of the two is known.
+    pub fn attribution(&self) -> Option<String> {
+        match (&self.author, &self.source) {
+            (Some(author), Some(source)) => Some(format!("{}, {}", author, source)),
+            (Some(one), None) | (None, Some(one)) => Some(one.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+// A line of the structured format. Everything but the text is optional.
+#[derive(Deserialize)]
+struct QuoteRecord {
+    id: Option<String>,
+    text: String,
+    source: Option<String>,
+    author: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    length_class: Option<LengthClass>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CorpusError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CorpusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// FNV-1a over the text with whitespace normalised, so reflowing a line keeps its id.
+pub fn content_id(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |b: u8| {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    };
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            feed(b' ');
+        }
+        word.bytes().for_each(&mut feed);
+    }
+    let mut id = format!("{:016x}", hash);
+    id.truncate(ID_LEN);
+    id
+}
+
+pub fn find_by_id<'a>(quotes: &'a [Quote], id: &str) -> Option<&'a Quote> {
+    quotes.iter().find(|q| q.id == id)
+}
+
+// `default_language` applies to quotes that don't name their own language.
+pub fn parse_corpus(raw: &str, default_language: &str) -> Result<Vec<Quote>, CorpusError> {
+    let mut quotes = Vec::new();
+    for (i, line) in raw.lines().enumerate() {
+        let line_number = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !trimmed.starts_with('{') {
+            quotes.push(Quote::from_text(None, trimmed, default_language));
+            continue;
+        }
+
+        let record: QuoteRecord = serde_json::from_str(trimmed).map_err(|e| CorpusError {
+            line: line_number,
+            message: e.to_string(),
+        })?;
+        if record.text.trim().is_empty() {
+            return Err(CorpusError {
+                line: line_number,
+                message: "empty text".to_string(),
+            });
+        }
+        let mut quote = Quote::from_text(record.id, record.text.trim(), default_language);
+        quote.source = record.source;
+        quote.author = record.author;
+        if let Some(language) = record.language {
+            quote.language = language;
+        }
+        quote.tags = record.tags;
+        if let Some(length_class) = record.length_class {
+            quote.length_class = length_class;
+        }
+        quotes.push(quote);
+    }
+
+    let scores: Vec<f64> = quotes.iter().map(|q| q.difficulty).collect();
+    for (quote, class) in quotes.iter_mut().zip(difficulty::buckets(&scores)) {
+        quote.difficulty_class = class;
+    }
+    Ok(quotes)
+}
+
+// Criteria for picking the next quote. `None` means any.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuoteFilter {
+    pub length: Option<LengthClass>,
+    pub tag: Option<String>,
+    pub difficulty: Option<Difficulty>,
+}
+
+impl QuoteFilter {
+    pub fn matches(&self, quote: &Quote) -> bool {
+        self.length.iter().all(|&l| quote.length_class == l)
+            && self.tag.iter().all(|t| quote.tags.contains(t))
+            && self.difficulty.iter().all(|&d| quote.difficulty_class == d)
+    }
+}
+
+// Indices into `quotes` that satisfy the filter, or every index if nothing does,
+// so a too-narrow filter never leaves the user without a quote. Long texts are
+// only ever started from their first section.
+pub fn candidates(quotes: &[Quote], filter: &QuoteFilter) -> Vec<usize> {
+    let starts: Vec<usize> = (0..quotes.len())
+        .filter(|&i| quotes[i].section.as_ref().is_none_or(|s| s.index == 0))
         .collect();
+    let matching: Vec<usize> = starts.iter().copied().filter(|&i| filter.matches(&quotes[i])).collect();
+    if matching.is_empty() {
+        starts
+    } else {
+        matching
+    }
+}
+
+pub fn all_tags(quotes: &[Quote]) -> Vec<String> {
+    let mut tags: Vec<String> = quotes.iter().flat_map(|q| q.tags.iter().cloned()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+// Returns a `Quote` as JSON, see `QuoteRequest` for the query parameters.
+pub const QUOTE_ENDPOINT: &str = "/api/quote";
+// Returns every tag used in any pack, as a JSON array of strings.
+pub const TAGS_ENDPOINT: &str = "/api/tags";
+
+// Query parameters of the quote endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuoteRequest {
+    pub pack: Option<String>,
+    // A specific quote, instead of a random one
+    pub id: Option<String>,
+    // Not to be picked again, usually the quote just typed
+    pub exclude: Option<String>,
+    pub length: Option<LengthClass>,
+    pub tag: Option<String>,
+    pub difficulty: Option<Difficulty>,
+    // Picks the quote deterministically instead of at random, see `selection`
+    pub seed: Option<u64>,
+    // The quote of the day for this day, see `selection::quote_of_the_day`
+    pub daily: Option<u64>,
+}
+
+impl QuoteRequest {
+    pub fn random(pack: &str, filter: &QuoteFilter, exclude: Option<&str>) -> Self {
+        QuoteRequest {
+            pack: Some(pack.to_string()),
+            id: None,
+            exclude: exclude.map(str::to_string),
+            length: filter.length,
+            tag: filter.tag.clone(),
+            difficulty: filter.difficulty,
+            seed: None,
+            daily: None,
+        }
+    }
+
+    // The next quote of a shared seed. The user's filter and recent quotes are left out,
+    // so everyone following the seed gets the same sequence whatever their settings.
+    pub fn seeded(pack: &str, seed: u64) -> Self {
+        QuoteRequest { pack: Some(pack.to_string()), seed: Some(seed), ..Default::default() }
+    }
+
+    // A quote from the pack, a section of a long text, or a generated test recreated
+    // from its id, see `words::generated_quote`
+    pub fn by_id(pack: &str, id: &str) -> Self {
+        QuoteRequest { pack: Some(pack.to_string()), id: Some(id.to_string()), ..Default::default() }
+    }
+
+    pub fn filter(&self) -> QuoteFilter {
+        QuoteFilter {
+            length: self.length,
+            tag: self.tag.clone(),
+            difficulty: self.difficulty,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        // Enum values are sent the way serde names them, e.g. "short"
+        let name = |value: serde_json::Value| value.as_str().map(str::to_string);
+        let params = [
+            ("pack", self.pack.clone()),
+            ("id", self.id.clone()),
+            ("exclude", self.exclude.clone()),
+            ("length", self.length.and_then(|l| name(serde_json::json!(l)))),
+            ("tag", self.tag.clone()),
+            ("difficulty", self.difficulty.and_then(|d| name(serde_json::json!(d)))),
+            ("seed", self.seed.map(|s| s.to_string())),
+            ("daily", self.daily.map(|d| d.to_string())),
+        ];
+        let query: Vec<String> = params
+            .iter()
+            .filter_map(|(key, value)| value.as_ref().map(|v| format!("{}={}", key, percent_encode(v))))
+            .collect();
+        if query.is_empty() {
+            QUOTE_ENDPOINT.to_string()
+        } else {
+            format!("{}?{}", QUOTE_ENDPOINT, query.join("&"))
+        }
+    }
+
+    // `random` in [0, 1) picks among the candidates, so callers bring their own randomness.
+    pub fn choose<'a>(&self, quotes: &'a [Quote], random: f64) -> Option<&'a Quote> {
+        if let Some(id) = &self.id {
+            return find_by_id(quotes, id);
+        }
+        if let Some(day) = self.daily {
+            return selection::quote_of_the_day(quotes, day);
+        }
+        let mut indices = candidates(quotes, &self.filter());
+        if indices.len() > 1 {
+            indices.retain(|&i| Some(&quotes[i].id) != self.exclude.as_ref());
+        }
+        let pick = (random * indices.len() as f64) as usize;
+        indices.get(pick.min(indices.len().saturating_sub(1))).map(|&i| &quotes[i])
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut out = String::new();
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_packs() -> Vec<Pack> {
+        PACK_SOURCES
+            .iter()
+            .map(|source| {
+                let raw = source.read(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+                Pack::parse(source, &raw).unwrap_or_else(|e| panic!("{}: {}", source.id, e))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_packs_parse() {
+        for pack in read_packs() {
+            assert!(!pack.quotes.is_empty(), "{}", pack.id);
+            assert!(pack.quotes.iter().all(|q| q.language == pack.language), "{}", pack.id);
+        }
+        assert_eq!(pack_source("german").unwrap().language, "de");
+        assert_eq!(pack_source_or_default(Some("klingon")).id, DEFAULT_PACK);
+        assert_eq!(pack_source_or_default(None).id, DEFAULT_PACK);
+    }
+
+    #[test]
+    fn test_plain_text_lines() {
+        let quotes = parse_corpus("first quote\n\nsecond quote\n", DEFAULT_LANGUAGE).unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[1].id, content_id("second quote"));
+        assert_eq!(quotes[1].text, "second quote");
+        assert_eq!(quotes[1].language, "en");
+        assert_eq!(quotes[1].length_class, LengthClass::Short);
+        assert_eq!(quotes[1].attribution(), None);
+    }
+
+    #[test]
+    fn test_structured_lines() {
+        let raw = r#"plain one
+{"id": "gettysburg", "text": "Four score and seven years ago", "author": "Abraham Lincoln", "source": "Gettysburg Address", "tags": ["speech"], "length_class": "long"}
+{"text": "Hola mundo", "language": "es"}"#;
+        let quotes = parse_corpus(raw, DEFAULT_LANGUAGE).unwrap();
+        assert_eq!(quotes.len(), 3);
+        assert_eq!(quotes[1].id, "gettysburg");
+        assert_eq!(quotes[1].attribution().unwrap(), "Abraham Lincoln, Gettysburg Address");
+        assert_eq!(quotes[1].tags, vec!["speech"]);
+        assert_eq!(quotes[1].length_class, LengthClass::Long);
+        assert_eq!(quotes[2].id, content_id("Hola mundo"));
+        assert_eq!(quotes[2].language, "es");
+    }
+
+    #[test]
+    fn test_ids_are_stable() {
+        let before = parse_corpus("alpha beta\ngamma delta", DEFAULT_LANGUAGE).unwrap();
+        let after = parse_corpus("new quote\ngamma  delta\n\nalpha beta", DEFAULT_LANGUAGE).unwrap();
+        assert_eq!(find_by_id(&after, &before[0].id).unwrap().text, "alpha beta");
+        assert_eq!(find_by_id(&after, &before[1].id).unwrap().text, "gamma  delta");
+        assert_ne!(before[0].id, before[1].id);
+        assert_eq!(before[0].id.len(), ID_LEN);
+        assert!(find_by_id(&after, "no-such-quote").is_none());
+    }
+
+    #[test]
+    fn test_pack_ids_are_unique() {
+        let packs = read_packs();
+        let mut ids: Vec<&str> = packs.iter().flat_map(|p| p.quotes.iter().map(|q| q.id.as_str())).collect();
+        let total = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), total);
+    }
+
+    #[test]
+    fn test_difficulty_buckets() {
+        let quotes = parse_corpus("the cat sat on the mat\nThe QUICK fox, aged 7, jumped!\nthe dog ran home", DEFAULT_LANGUAGE).unwrap();
+        assert_eq!(quotes[1].difficulty_class, Difficulty::Hard);
+        let by_difficulty = QuoteFilter { difficulty: Some(Difficulty::Hard), ..Default::default() };
+        assert_eq!(candidates(&quotes, &by_difficulty), vec![1]);
+        let english = &read_packs()[0];
+        for d in Difficulty::ALL {
+            assert!(english.quotes.iter().any(|q| q.difficulty_class == d));
+        }
+    }
+
+    #[test]
+    fn test_malformed_line_reports_line_number() {
+        let err = parse_corpus("ok\n\n{\"author\": \"no text\"}", DEFAULT_LANGUAGE).unwrap_err();
+        assert_eq!(err.line, 3);
+        let err = parse_corpus("{\"text\": \"  \"}", DEFAULT_LANGUAGE).unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+
+    #[test]
+    fn test_filter_falls_back_to_everything() {
+        let quotes = parse_corpus("{\"text\": \"a\", \"tags\": [\"x\"]}\nb", DEFAULT_LANGUAGE).unwrap();
+        let by_tag = QuoteFilter { tag: Some("x".to_string()), ..Default::default() };
+        assert_eq!(candidates(&quotes, &by_tag), vec![0]);
+        let by_missing_tag = QuoteFilter { tag: Some("y".to_string()), ..Default::default() };
+        assert_eq!(candidates(&quotes, &by_missing_tag), vec![0, 1]);
+        assert_eq!(all_tags(&quotes), vec!["x"]);
+    }
+
+    #[test]
+    fn test_request_url() {
+        assert_eq!(QuoteRequest::default().url(), "/api/quote");
+        let filter = QuoteFilter {
+            length: Some(LengthClass::Short),
+            tag: Some("new york".to_string()),
+            difficulty: Some(Difficulty::Hard),
+        };
+        assert_eq!(
+            QuoteRequest::random("german", &filter, Some("abc")).url(),
+            "/api/quote?pack=german&exclude=abc&length=short&tag=new%20york&difficulty=hard"
+        );
+        assert_eq!(QuoteRequest::seeded("english", 42).url(), "/api/quote?pack=english&seed=42");
+    }
+
+    #[test]
+    fn test_request_choose() {
+        let quotes = parse_corpus("one\ntwo\n{\"id\": \"three\", \"text\": \"three\"}", DEFAULT_LANGUAGE).unwrap();
+        let by_id = QuoteRequest { id: Some("three".to_string()), ..Default::default() };
+        assert_eq!(by_id.choose(&quotes, 0.0).unwrap().text, "three");
+        let missing = QuoteRequest { id: Some("four".to_string()), ..Default::default() };
+        assert!(missing.choose(&quotes, 0.0).is_none());
+
+        let excluding = QuoteRequest { exclude: Some(quotes[0].id.clone()), ..Default::default() };
+        for random in [0.0, 0.5, 0.999] {
+            assert_ne!(excluding.choose(&quotes, random).unwrap().text, "one");
+        }
+        // The only quote is still returned even if excluded
+        let single = &quotes[..1];
+        assert_eq!(excluding.choose(single, 0.3).unwrap().text, "one");
+        assert!(QuoteRequest::default().choose(&[], 0.3).is_none());
+
+        let daily = QuoteRequest { daily: Some(20_000), ..Default::default() };
+        assert_eq!(daily.url(), "/api/quote?daily=20000");
+        for random in [0.0, 0.5, 0.999] {
+            assert_eq!(daily.choose(&quotes, random), selection::quote_of_the_day(&quotes, 20_000));
+        }
+    }
 }
@@ -1,4 +1,13 @@
 use yew::prelude::*;
+use super::anticheat::Verdict;
+use super::drill::weak_spots;
+use super::keybindings::Action;
+use super::matching::align_incremental;
+use super::metrics::{SpeedProfile, HISTOGRAM_BUCKET_WPM, HISTOGRAM_MAX_WPM};
+use super::sections::{Section, SectionResult};
+use super::settings::use_settings;
+use super::text_view::{self, Word};
+use super::theme::{use_theme, with_alpha};
 
 #[derive(Properties, PartialEq)]
 pub struct TypingResultsProps {
@@ -10,11 +19,24 @@ pub struct TypingResultsProps {
     pub total_words: usize,
     pub error_count: usize,
     pub keystroke_times: Vec<f64>,
+    // Gaps between every character typed, including deleted ones
+    pub typed_intervals: Vec<f64>,
     pub start_time: Option<f64>,
     pub error_positions: Vec<usize>,
     pub current_quote: String,
     pub user_input: String,
     pub key_log: String,
+    pub verdict: Verdict,
+    pub on_drill: Callback<Vec<String>>,
+    // Set when the text was a section of a longer one
+    pub section: Option<Section>,
+    pub section_results: Vec<Option<SectionResult>>,
+    pub on_continue: Option<Callback<()>>,
+    // Active transforms, so results on transformed text aren't taken for plain ones
+    pub transforms: String,
+    // Show the text with every mistake marked, after typing without seeing them
+    #[prop_or_default]
+    pub reveal_alignment: bool,
 }
 
 fn get_word_at_index(input: &str, index: usize) -> String {
@@ -55,7 +77,21 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
     let start_time = props.start_time;
     let error_positions = &props.error_positions;
     
+    let key_bindings = use_settings().settings.key_bindings;
+    let theme = use_theme();
+    let wpm_colour = format!("color: {};", theme.chart_wpm);
+    let cpm_colour = format!("color: {};", theme.chart_cpm);
+    let button_style = format!("background-color: {}; color: {};", theme.accent, theme.background);
+    let key_style = format!("background-color: {};", with_alpha(&theme.pending, 0.15));
+    // Unbound actions are left out of the hint
+    let next_keys: Vec<String> = [Action::Finish, Action::NewQuote]
+        .iter()
+        .map(|&action| key_bindings.describe(action))
+        .filter(|keys| !keys.is_empty())
+        .collect();
     let chart_ref = use_node_ref();
+    let profile = SpeedProfile::from_keystrokes(keystroke_times, error_positions, &props.typed_intervals);
+    let weak = weak_spots(&props.current_quote, &props.user_input, keystroke_times, error_positions);
     let hovered_stats = use_state(|| None::<(f64, f64, String)>);
 
     // Calculate timeline data (WPM/CPM at each point, counting only correct characters)
@@ -151,6 +187,22 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
         })
     };
 
+    // The text with every mistake marked, for blind mode
+    let revealed = if props.reveal_alignment {
+        let states = text_view::char_states(&props.current_quote, &align_incremental(&props.current_quote, &props.user_input));
+        let words = text_view::word_states(&states);
+        html! {
+            <div class="mb-6 p-4 rounded font-mono text-xl flex flex-wrap"
+                 style={format!("background-color: {}; line-height: 2.2;", theme.background)}>
+                { for words.iter().map(|w| html! {
+                    <Word key={w.span.start} chars={states[w.span.clone()].to_vec()} cursor={None::<usize>} />
+                }) }
+            </div>
+        }
+    } else {
+        html! {}
+    };
+
     // Find max values for scaling the chart
     let max_wpm = timeline_data.iter().map(|(w, _, _, _)| *w).fold(0.0f64, f64::max);
     let max_cpm = timeline_data.iter().map(|(_, i, c, _)| i.max(*c)).fold(0.0f64, f64::max);
@@ -170,14 +222,28 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
         <div class="mb-8 p-8 bg-gray-100 dark:bg-gray-800 rounded-lg">
             <h3 class="text-3xl font-bold mb-6 text-center">{"Results"}</h3>
 
+            if !props.transforms.is_empty() {
+                <div class="mb-6 text-center text-sm text-gray-500 dark:text-gray-400">
+                    {format!("Transforms: {}", props.transforms)}
+                </div>
+            }
+
+            if !props.verdict.is_verified() {
+                <div class="mb-6 p-2 text-center text-sm rounded"
+                     style={format!("background-color: {}; color: {};", with_alpha(&theme.error, 0.15), theme.error)}>
+                    {"Unverified result: "}
+                    {props.verdict.flags.iter().map(|f| f.description()).collect::<Vec<_>>().join(", ")}
+                </div>
+            }
+
             // Main stats grid
             <div class="grid grid-cols-3 gap-4 text-center mb-6">
                 <div>
-                    <div class="text-4xl font-bold text-blue-500">{format!("{:.0}", props.wpm)}</div>
+                    <div class="text-4xl font-bold" style={wpm_colour.clone()}>{format!("{:.0}", props.wpm)}</div>
                     <div class="text-gray-600 dark:text-gray-400 text-sm">{"WPM"}</div>
                 </div>
                 <div>
-                    <div class="text-4xl font-bold text-cyan-500">{format!("{:.0}", props.cpm)}</div>
+                    <div class="text-4xl font-bold" style={cpm_colour.clone()}>{format!("{:.0}", props.cpm)}</div>
                     <div class="text-gray-600 dark:text-gray-400 text-sm">{"CPM"}</div>
                 </div>
                 <div>
@@ -186,6 +252,8 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                 </div>
             </div>
 
+            {revealed}
+
             // Secondary stats
             <div class="grid grid-cols-4 gap-4 text-center mb-6 text-sm">
                 <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
@@ -197,7 +265,7 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                     <div class="text-gray-600 dark:text-gray-400">{"Words"}</div>
                 </div>
                 <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
-                    <div class="text-xl font-bold text-red-500">{props.error_count}</div>
+                    <div class="text-xl font-bold" style={format!("color: {};", theme.error)}>{props.error_count}</div>
                     <div class="text-gray-600 dark:text-gray-400">{"Errors"}</div>
                 </div>
                 <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
@@ -206,6 +274,22 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                 </div>
             </div>
 
+            // Speed profile: steady vs bursty typing
+            <div class="grid grid-cols-3 gap-4 text-center mb-6 text-sm">
+                <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
+                    <div class="text-xl font-bold">{format!("{:.0}", profile.raw_wpm)}</div>
+                    <div class="text-gray-600 dark:text-gray-400">{"Raw WPM"}</div>
+                </div>
+                <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
+                    <div class="text-xl font-bold">{format!("{:.0}", profile.burst_wpm)}</div>
+                    <div class="text-gray-600 dark:text-gray-400">{"Burst WPM"}</div>
+                </div>
+                <div class="bg-gray-200 dark:bg-gray-700 rounded p-2">
+                    <div class="text-xl font-bold">{format!("{:.0}%", profile.consistency)}</div>
+                    <div class="text-gray-600 dark:text-gray-400">{"Consistency"}</div>
+                </div>
+            </div>
+
             // Timeline chart
             <div class="mb-4">
                 <div class="text-sm text-gray-500 dark:text-gray-400 mb-2">{"Speed Timeline"}</div>
@@ -232,8 +316,8 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                         // Tooltip
                         if let Some((wpm, cpm, word)) = &*hovered_stats {
                              <div class="absolute top-2 right-2 bg-white/90 dark:bg-black/80 p-2 rounded shadow text-xs pointer-events-none z-10 border border-gray-200 dark:border-gray-600">
-                                 <div class="text-blue-500 font-bold">{format!("WPM: {:.0}", wpm)}</div>
-                                 <div class="text-cyan-500 font-bold">{format!("CPM: {:.0}", cpm)}</div>
+                                 <div class="font-bold" style={wpm_colour.clone()}>{format!("WPM: {:.0}", wpm)}</div>
+                                 <div class="font-bold" style={cpm_colour.clone()}>{format!("CPM: {:.0}", cpm)}</div>
                                  <div class="text-gray-600 dark:text-gray-400 mt-1">{format!("\"{}\"", word)}</div>
                              </div>
                         }
@@ -245,14 +329,14 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                                 let y_pos = 100.0 - (wpm_val / chart_max * 90.0).min(95.0);
                                 html! {
                                     <line x1="0" y1={format!("{:.1}", y_pos)} x2="100" y2={format!("{:.1}", y_pos)}
-                                          stroke="#4b5563" stroke-width="0.1" stroke-dasharray="0.5,0.5" />
+                                          stroke={theme.chart_grid.clone()} stroke-width="0.1" stroke-dasharray="0.5,0.5" />
                                 }
                             }).collect::<Html>()}
 
-                            // Cumulative WPM line (blue)
+                            // Cumulative WPM line
                             <polyline
                                 fill="none"
-                                stroke="#3b82f6"
+                                stroke={theme.chart_wpm.clone()}
                                 stroke-width="0.5"
                                 points={timeline_data.iter().enumerate().map(|(i, (cum_wpm, _, _, _))| {
                                     let x = (i as f64 / timeline_data.len() as f64) * 100.0;
@@ -260,10 +344,10 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                                     format!("{:.1},{:.1}", x, y)
                                 }).collect::<Vec<_>>().join(" ")}
                             />
-                            // Instantaneous CPM line (cyan, scaled to WPM equivalent)
+                            // Instantaneous CPM line, scaled to WPM equivalent
                             <polyline
                                 fill="none"
-                                stroke="#06b6d4"
+                                stroke={theme.chart_cpm.clone()}
                                 stroke-width="0.3"
                                 stroke-opacity="0.6"
                                 points={timeline_data.iter().enumerate().map(|(i, (_, inst_cpm, _, _))| {
@@ -276,8 +360,8 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
 
                         // Legend
                         <div class="absolute top-1 right-1 text-xs flex gap-2">
-                            <span class="text-blue-500">{"WPM"}</span>
-                            <span class="text-cyan-500">{"CPM"}</span>
+                            <span style={wpm_colour}>{"WPM"}</span>
+                            <span style={cpm_colour}>{"CPM"}</span>
                         </div>
                     </div>
 
@@ -300,9 +384,9 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                     <div class="flex-grow flex bg-gray-200 dark:bg-gray-700 rounded overflow-hidden">
                         {timeline_data.iter().map(|(_, _, _, is_error)| {
                             let width_pct = 100.0 / timeline_data.len() as f64;
-                            let color = if *is_error { "bg-red-500" } else { "bg-transparent" };
+                            let color = if *is_error { theme.chart_error.as_str() } else { "transparent" };
                             html! {
-                                <div class={color} style={format!("width: {}%;", width_pct)}></div>
+                                <div style={format!("width: {}%; background-color: {};", width_pct, color)}></div>
                             }
                         }).collect::<Html>()}
                     </div>
@@ -310,6 +394,88 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                 </div>
             </div>
 
+            // Speed histogram
+            if !profile.histogram.is_empty() {
+                <div class="mb-4">
+                    <div class="text-sm text-gray-500 dark:text-gray-400 mb-2">{"Speed Distribution"}</div>
+                    <div class="flex flex-row items-end h-20 gap-px select-none">
+                        {{
+                            let max_count = profile.histogram.iter().map(|(_, c)| *c).max().unwrap_or(1).max(1);
+                            profile.histogram.iter().map(|(lower, count)| {
+                                let height_pct = *count as f64 / max_count as f64 * 100.0;
+                                let title = if *lower >= HISTOGRAM_MAX_WPM {
+                                    format!("\u{2265} {:.0} WPM: {}", lower, count)
+                                } else {
+                                    format!("{:.0}-{:.0} WPM: {}", lower, lower + HISTOGRAM_BUCKET_WPM, count)
+                                };
+                                html! {
+                                    <div class="flex-grow rounded-t" {title}
+                                         style={format!("height: {:.1}%; background-color: {};", height_pct, with_alpha(&theme.chart_wpm, 0.7))}></div>
+                                }
+                            }).collect::<Html>()
+                        }}
+                    </div>
+                    <div class="flex justify-between text-[10px] text-gray-400">
+                        <span>{"0"}</span>
+                        <span>{match profile.histogram.last() {
+                            Some((lower, _)) if *lower >= HISTOGRAM_MAX_WPM => format!("\u{2265} {:.0} WPM", lower),
+                            _ => format!("{:.0} WPM", profile.histogram.len() as f64 * HISTOGRAM_BUCKET_WPM),
+                        }}</span>
+                    </div>
+                </div>
+            }
+
+            if let Some(section) = &props.section {
+                <div class="mb-4">
+                    <div class="flex justify-between items-center mb-2">
+                        <div class="text-sm text-gray-500 dark:text-gray-400">{section.label()}</div>
+                        if let Some(on_continue) = &props.on_continue {
+                            <button class="px-4 py-2 rounded text-sm" style={button_style.clone()}
+                                    onclick={on_continue.reform(|_: MouseEvent| ())}>
+                                {"Continue to next section"}
+                            </button>
+                        }
+                    </div>
+                    <div class="flex flex-wrap gap-1 text-xs font-mono">
+                        { for props.section_results.iter().enumerate().map(|(i, result)| {
+                            let current = i == section.index;
+                            let style = if current {
+                                format!("background-color: {};", with_alpha(&theme.accent, 0.25))
+                            } else {
+                                key_style.clone()
+                            };
+                            html! {
+                                <div class="px-2 py-1 rounded" {style}>
+                                    {match result {
+                                        Some(r) => format!("{}: {:.0} wpm, {:.0}%", i + 1, r.wpm, r.accuracy),
+                                        None => format!("{}: \u{2014}", i + 1),
+                                    }}
+                                </div>
+                            }
+                        }) }
+                    </div>
+                </div>
+            }
+
+            if !weak.is_empty() {
+                <div class="mb-4 flex justify-between items-center">
+                    <div class="text-sm text-gray-500 dark:text-gray-400">
+                        {"Weak spots: "}
+                        { for weak.iter().map(|w| html! {
+                            <kbd class="mx-0.5 px-2 py-1 rounded text-xs font-mono" style={key_style.clone()}>{w}</kbd>
+                        }) }
+                    </div>
+                    <button class="px-4 py-2 rounded text-sm" style={button_style.clone()}
+                            onclick={{
+                                let on_drill = props.on_drill.clone();
+                                let weak = weak.clone();
+                                Callback::from(move |_: MouseEvent| on_drill.emit(weak.clone()))
+                            }}>
+                        {"Practise my weak keys"}
+                    </button>
+                </div>
+            }
+
             // Debug Window
             <div class="mt-8 p-4 bg-gray-200 dark:bg-gray-900 rounded text-xs font-mono overflow-auto max-h-40 whitespace-pre-wrap">
                 <div class="font-bold mb-2 border-b border-gray-400 pb-1">{"Debug Info"}</div>
@@ -319,9 +485,18 @@ pub fn TypingResults(props: &TypingResultsProps) -> Html {
                 <div><span class="font-bold text-gray-600 dark:text-gray-400">{"Errors: "}</span>{props.error_count}</div>
             </div>
 
-            <div class="text-center text-gray-500 dark:text-gray-400 text-sm mt-4">
-                {"Press "}<kbd class="px-2 py-1 bg-gray-200 dark:bg-gray-700 rounded text-xs">{"ESC"}</kbd>{" or "}<kbd class="px-2 py-1 bg-gray-200 dark:bg-gray-700 rounded text-xs">{"TAB"}</kbd>{" for next quote"}
-            </div>
+            if !next_keys.is_empty() {
+                <div class="text-center text-gray-500 dark:text-gray-400 text-sm mt-4">
+                    {"Press "}
+                    { for next_keys.iter().enumerate().map(|(i, keys)| html! {
+                        <>
+                            if i > 0 { {" or "} }
+                            <kbd class="px-2 py-1 rounded text-xs" style={key_style.clone()}>{keys}</kbd>
+                        </>
+                    }) }
+                    {" for next quote"}
+                </div>
+            }
         </div>
     }
 }
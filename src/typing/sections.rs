@@ -0,0 +1,232 @@
+// Long texts split into sections that can be typed one at a time.
+//
+// Texts are cut at sentence boundaries into sections of about a target length. Each
+// section is a quote of its own, with an id derived from the whole text's id and its
+// position, so a section can be linked and "continue to next section" knows where to go.
+// Results are kept per section in local storage, so a long text can be typed over
+// several sessions.
+
+use std::collections::HashMap;
+
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+use super::difficulty;
+use super::quotes::{LengthClass, Quote};
+
+const STORAGE_KEY: &str = "thockflow.sections";
+
+// Characters that end a sentence when followed by whitespace.
+pub const SENTENCE_ENDS: [char; 3] = ['.', '!', '?'];
+
+// Corpus texts longer than this are split, the rest are typed whole.
+pub const SPLIT_OVER_CHARS: usize = 1500;
+// Target length of the sections of a corpus text.
+pub const SECTION_CHARS: usize = 600;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    // Id of the whole text
+    pub text_id: String,
+    pub index: usize,
+    pub count: usize,
+}
+
+impl Section {
+    // e.g. "3f2a9c0d1b7e-2" for the second section
+    pub fn id(text_id: &str, index: usize) -> String {
+        format!("{}-{}", text_id, index + 1)
+    }
+
+    pub fn next_id(&self) -> Option<String> {
+        (self.index + 1 < self.count).then(|| Section::id(&self.text_id, self.index + 1))
+    }
+
+    pub fn label(&self) -> String {
+        format!("Section {} of {}", self.index + 1, self.count)
+    }
+}
+
+// Splits normalised text into sentences, keeping the closing punctuation.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if SENTENCE_ENDS.contains(&c) && matches!(chars.peek(), Some((_, ' '))) {
+            result.push(text[start..=i].trim());
+            start = i + 1;
+        }
+    }
+    if !text[start..].trim().is_empty() {
+        result.push(text[start..].trim());
+    }
+    result
+}
+
+// Groups whole sentences into sections of about `target` characters. A section only
+// goes over the target when a single sentence does, and then that sentence is split
+// between words.
+pub fn chunk(text: &str, target: usize) -> Vec<String> {
+    let mut sections = Vec::new();
+    let mut current = String::new();
+    let mut push = |current: &mut String, piece: &str| {
+        if !current.is_empty() && current.chars().count() + 1 + piece.chars().count() > target {
+            sections.push(std::mem::take(current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(piece);
+    };
+
+    for sentence in sentences(text) {
+        if sentence.chars().count() <= target {
+            push(&mut current, sentence);
+            continue;
+        }
+        for word in sentence.split(' ') {
+            push(&mut current, word);
+        }
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+// Splits the quote into sections of about `target` characters, which keep its
+// attribution, tags and difficulty class.
+pub fn split(quote: &Quote, target: usize) -> Vec<Quote> {
+    let texts = chunk(&quote.text, target);
+    let count = texts.len();
+    texts
+        .into_iter()
+        .enumerate()
+        .map(|(index, text)| Quote {
+            id: Section::id(&quote.id, index),
+            length_class: LengthClass::for_text(&text),
+            difficulty: difficulty::difficulty(&text),
+            section: Some(Section { text_id: quote.id.clone(), index, count }),
+            text,
+            ..quote.clone()
+        })
+        .collect()
+}
+
+// Replaces the texts that are too long for one test with their sections.
+pub fn split_long(quotes: Vec<Quote>) -> Vec<Quote> {
+    quotes
+        .into_iter()
+        .flat_map(|quote| {
+            if quote.text.chars().count() > SPLIT_OVER_CHARS {
+                split(&quote, SECTION_CHARS)
+            } else {
+                vec![quote]
+            }
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionResult {
+    pub wpm: f64,
+    pub accuracy: f64,
+}
+
+// The latest result of every section typed, per text.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    texts: HashMap<String, Vec<Option<SectionResult>>>,
+}
+
+impl Progress {
+    pub fn load() -> Self {
+        LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = LocalStorage::set(STORAGE_KEY, self) {
+            log::warn!("failed to save section progress: {}", e);
+        }
+    }
+
+    pub fn record(&mut self, section: &Section, result: SectionResult) {
+        let results = self.texts.entry(section.text_id.clone()).or_default();
+        results.resize(section.count.max(results.len()), None);
+        results[section.index] = Some(result);
+    }
+
+    // One entry per section of the text, None for those not typed yet
+    pub fn results(&self, section: &Section) -> Vec<Option<SectionResult>> {
+        let mut results = self.texts.get(&section.text_id).cloned().unwrap_or_default();
+        results.resize(section.count, None);
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        assert_eq!(
+            sentences("First one. Second one! Third? v1.2 stays whole"),
+            vec!["First one.", "Second one!", "Third?", "v1.2 stays whole"]
+        );
+    }
+
+    #[test]
+    fn test_chunk_keeps_sentences_together() {
+        let text = "Alpha beta gamma. Delta epsilon. Zeta eta theta iota. Kappa.";
+        let sections = chunk(text, 35);
+        assert_eq!(sections, vec!["Alpha beta gamma. Delta epsilon.", "Zeta eta theta iota. Kappa."]);
+        assert_eq!(sections.join(" "), text);
+    }
+
+    #[test]
+    fn test_chunk_splits_long_sentences_between_words() {
+        let text = "one two three four five six seven eight nine ten";
+        let sections = chunk(text, 15);
+        assert!(sections.iter().all(|s| s.chars().count() <= 15));
+        assert_eq!(sections.join(" "), text);
+    }
+
+    #[test]
+    fn test_split_long() {
+        let sentence = "This sentence is exactly fifty characters long ok. ";
+        let long = sentence.repeat(40);
+        let quotes = vec![
+            Quote::from_text(Some("short".to_string()), "A short one.", "en"),
+            Quote::from_text(Some("essay".to_string()), long.trim(), "en"),
+        ];
+        let split = split_long(quotes);
+        assert_eq!(split[0].id, "short");
+        assert!(split[0].section.is_none());
+
+        let sections = &split[1..];
+        assert!(sections.len() > 1);
+        assert!(sections.iter().all(|q| q.text.chars().count() <= SECTION_CHARS));
+        assert!(sections.iter().all(|q| q.text.ends_with('.')));
+        assert_eq!(sections.iter().map(|q| q.text.as_str()).collect::<Vec<_>>().join(" "), long.trim());
+        let first = sections[0].section.as_ref().unwrap();
+        assert_eq!(sections[0].id, "essay-1");
+        assert_eq!(first.label(), format!("Section 1 of {}", sections.len()));
+        assert_eq!(first.next_id().as_deref(), Some("essay-2"));
+        assert_eq!(sections.last().unwrap().section.as_ref().unwrap().next_id(), None);
+    }
+
+    #[test]
+    fn test_progress() {
+        let section = |index| Section { text_id: "essay".to_string(), index, count: 3 };
+        let mut progress = Progress::default();
+        assert_eq!(progress.results(&section(0)), vec![None, None, None]);
+        let result = SectionResult { wpm: 80.0, accuracy: 97.0 };
+        progress.record(&section(1), result);
+        assert_eq!(progress.results(&section(0)), vec![None, Some(result), None]);
+        let retry = SectionResult { wpm: 90.0, accuracy: 99.0 };
+        progress.record(&section(1), retry);
+        assert_eq!(progress.results(&section(2))[1], Some(retry));
+    }
+}
@@ -0,0 +1,91 @@
+// Seeded quote selection, shared by the server and the client so both pick the same quote.
+//
+// A seed always picks the same quote from the same pack, and the next seed is derived
+// from it, so everyone opening `?seed=<n>` gets the same sequence of tests and can compare
+// results fairly. Seeded picks ignore the quote filter and the practice mode, see
+// `QuoteRequest::seeded`. The quote of the day is seeded with the UTC date.
+
+use time::{Date, Duration, OffsetDateTime};
+
+use super::quotes::{Quote, QuoteRequest};
+use super::words::Rng;
+
+const DAY_MS: f64 = 86_400_000.0;
+
+// Mixed into the daily seed, so the quote of the day isn't the same as `?seed=<day>`
+const DAILY_SALT: u64 = 0x6461_696c_7921;
+
+// Days since the Unix epoch, in UTC
+pub fn utc_day(unix_ms: f64) -> u64 {
+    (unix_ms / DAY_MS).floor().max(0.0) as u64
+}
+
+// e.g. "2026-10-18"
+pub fn date_label(day: u64) -> String {
+    date(day).to_string()
+}
+
+fn date(day: u64) -> Date {
+    OffsetDateTime::UNIX_EPOCH.date() + Duration::days(day as i64)
+}
+
+// Uniform in [0, 1), the same for the same seed
+pub fn seeded_random(seed: u64) -> f64 {
+    Rng::new(seed).next_f64()
+}
+
+// Seeds are kept to 32 bits so they stay short in links and exact in JavaScript
+pub fn next_seed(seed: u64) -> u64 {
+    Rng::new(seed).next_u64() >> 32
+}
+
+pub fn quote_of_the_day(quotes: &[Quote], day: u64) -> Option<&Quote> {
+    QuoteRequest::default().choose(quotes, seeded_random(day ^ DAILY_SALT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotes() -> Vec<Quote> {
+        (0..50)
+            .map(|i| Quote::from_text(None, &format!("Quote number {} has some words in it.", i), "en"))
+            .collect()
+    }
+
+    #[test]
+    fn test_utc_day() {
+        assert_eq!(utc_day(0.0), 0);
+        assert_eq!(utc_day(DAY_MS - 1.0), 0);
+        assert_eq!(utc_day(DAY_MS), 1);
+        // 2026-10-18T23:59:59Z and the next second
+        assert_eq!(date_label(utc_day(1_792_367_999_000.0)), "2026-10-18");
+        assert_eq!(date_label(utc_day(1_792_368_000_000.0)), "2026-10-19");
+    }
+
+    #[test]
+    fn test_quote_of_the_day_is_stable() {
+        let quotes = quotes();
+        let day = utc_day(1_792_367_999_000.0);
+        assert_eq!(quote_of_the_day(&quotes, day), quote_of_the_day(&quotes, day));
+        let week: Vec<&str> = (day..day + 7).map(|d| quote_of_the_day(&quotes, d).unwrap().id.as_str()).collect();
+        assert!(week.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(quote_of_the_day(&[], day), None);
+    }
+
+    #[test]
+    fn test_seeds_give_the_same_sequence() {
+        let quotes = quotes();
+        let sequence = |mut seed: u64| {
+            (0..5)
+                .map(|_| {
+                    seed = next_seed(seed);
+                    assert!(seed <= u32::MAX as u64);
+                    QuoteRequest::seeded("english", seed).choose(&quotes, seeded_random(seed)).unwrap().id.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(42), sequence(42));
+        assert_ne!(sequence(42), sequence(43));
+    }
+}
@@ -0,0 +1,119 @@
+// User settings, persisted in local storage and shared through a yew context.
+
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use super::caret::CaretOptions;
+use super::hud::HudOptions;
+use super::keybindings::KeyBindings;
+use super::keyboard::KeyboardOptions;
+use super::layout::ViewOptions;
+use super::quotes::QuoteFilter;
+use super::sound::SoundOptions;
+use super::text_view::{DisplayPolicy, WordFeedback};
+use super::theme::{Theme, ThemeProvider};
+use super::transform::Transform;
+use super::words::{Mode, WordOptions};
+
+const STORAGE_KEY: &str = "thockflow.settings";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub key_bindings: KeyBindings,
+    pub quote_filter: QuoteFilter,
+    pub mode: Mode,
+    pub word_options: WordOptions,
+    // Applied to every text before it is played, see `transform`
+    pub transforms: Vec<Transform>,
+    // Name of a built-in or custom theme, see `theme::resolve`
+    pub theme: String,
+    pub custom_themes: Vec<Theme>,
+    pub caret: CaretOptions,
+    pub view: ViewOptions,
+    pub word_feedback: WordFeedback,
+    pub display_policy: DisplayPolicy,
+    // Live stats while typing
+    pub hud: HudOptions,
+    // Keypress sounds
+    pub sound: SoundOptions,
+    // On-screen keyboard under the text
+    pub keyboard: KeyboardOptions,
+}
+
+impl Settings {
+    // Falls back to defaults if nothing is stored or the stored value doesn't parse.
+    // Actions added after the settings were saved get their default bindings, see
+    // `KeyBindings`.
+    fn load() -> Self {
+        LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = LocalStorage::set(STORAGE_KEY, self) {
+            log::warn!("failed to save settings: {}", e);
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SettingsContext {
+    pub settings: Settings,
+    pub update: Callback<Settings>,
+    // False until the stored settings have been read, i.e. during the first render
+    pub loaded: bool,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsProviderProps {
+    pub children: Html,
+}
+
+#[function_component]
+pub fn SettingsProvider(props: &SettingsProviderProps) -> Html {
+    // Start from defaults so the hydrated tree matches the server render,
+    // then pick up the stored settings once mounted in the browser.
+    let settings = use_state(Settings::default);
+    let loaded = use_state(|| false);
+    {
+        let settings = settings.clone();
+        let loaded = loaded.clone();
+        use_effect_with((), move |_| {
+            settings.set(Settings::load());
+            loaded.set(true);
+            || ()
+        });
+    }
+
+    let update = {
+        let settings = settings.clone();
+        Callback::from(move |new_settings: Settings| {
+            new_settings.save();
+            settings.set(new_settings);
+        })
+    };
+
+    let context = SettingsContext {
+        settings: (*settings).clone(),
+        update,
+        loaded: *loaded,
+    };
+
+    html! {
+        <ContextProvider<SettingsContext> context={context}>
+            <ThemeProvider>
+                {props.children.clone()}
+            </ThemeProvider>
+        </ContextProvider<SettingsContext>>
+    }
+}
+
+#[hook]
+pub fn use_settings() -> SettingsContext {
+    use_context::<SettingsContext>().unwrap_or_else(|| SettingsContext {
+        settings: Settings::default(),
+        update: Callback::noop(),
+        loaded: true,
+    })
+}
@@ -0,0 +1,652 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use super::caret::{CaretStyle, MAX_DURATION_MS};
+use super::difficulty::Difficulty;
+use super::hook::fetch_json;
+use super::hud::HudOptions;
+use super::keybindings::{Action, KeyBindings};
+use super::keyboard::{Layout, PhysicalLayout};
+use super::layout::MAX_VISIBLE_LINES;
+use super::quotes::{self, LengthClass};
+use super::settings::use_settings;
+use super::sound::SwitchProfile;
+use super::text_view::DisplayPolicy;
+use super::theme::{self, Slot, DEFAULT_THEME};
+use super::transform::Transform;
+use super::words::{Mode, WordList};
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsPanelProps {
+    // The pack lives in the URL rather than in settings, so the server renders the same one
+    pub pack_id: String,
+    pub on_select_pack: Callback<String>,
+}
+
+#[function_component]
+pub fn SettingsPanel(props: &SettingsPanelProps) -> Html {
+    let ctx = use_settings();
+    let binding_error = use_state(|| None::<String>);
+    let tags = use_state(Vec::<String>::new);
+    {
+        let tags = tags.clone();
+        use_effect_with((), move |_| {
+            yew::platform::spawn_local(async move {
+                match fetch_json::<Vec<String>>(quotes::TAGS_ENDPOINT).await {
+                    Ok(loaded) => tags.set(loaded),
+                    Err(e) => log::warn!("failed to load tags: {}", e),
+                }
+            });
+            || ()
+        });
+    }
+
+    let key_binding_rows = Action::ALL.iter().map(|&action| {
+        let onchange = {
+            let ctx = ctx.clone();
+            let binding_error = binding_error.clone();
+            Callback::from(move |e: Event| {
+                let value = e.target_unchecked_into::<HtmlInputElement>().value();
+                let mut settings = ctx.settings.clone();
+                match KeyBindings::parse_list(&value).and_then(|combos| settings.key_bindings.set(action, combos)) {
+                    Ok(()) => {
+                        ctx.update.emit(settings);
+                        binding_error.set(None);
+                    }
+                    Err(err) => binding_error.set(Some(format!("{}: {}", action.label(), err))),
+                }
+            })
+        };
+        html! {
+            <label class="flex justify-between items-center gap-4">
+                <span class="text-gray-600 dark:text-gray-400">{action.label()}</span>
+                <input type="text"
+                       class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 font-mono text-xs"
+                       value={ctx.settings.key_bindings.describe(action)}
+                       {onchange} />
+            </label>
+        }
+    }).collect::<Html>();
+
+    let on_reset_bindings = {
+        let ctx = ctx.clone();
+        let binding_error = binding_error.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut settings = ctx.settings.clone();
+            settings.key_bindings = KeyBindings::default();
+            ctx.update.emit(settings);
+            binding_error.set(None);
+        })
+    };
+
+    let on_length_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.quote_filter.length = LengthClass::ALL.into_iter().find(|l| l.label() == value);
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_difficulty_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.quote_filter.difficulty = Difficulty::ALL.into_iter().find(|d| d.label() == value);
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_tag_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.quote_filter.tag = if value.is_empty() { None } else { Some(value) };
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_mode_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.mode = Mode::ALL.into_iter().find(|m| m.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_word_list_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            if let Some(list) = WordList::ALL.into_iter().find(|l| l.label() == value) {
+                settings.word_options.list = list;
+            }
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_punctuation_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.word_options.punctuation = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_numbers_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.word_options.numbers = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let transform_rows = Transform::ALL.iter().map(|&transform| {
+        let onchange = {
+            let ctx = ctx.clone();
+            Callback::from(move |e: Event| {
+                let mut settings = ctx.settings.clone();
+                settings.transforms.retain(|t| *t != transform);
+                if e.target_unchecked_into::<HtmlInputElement>().checked() {
+                    settings.transforms.push(transform);
+                }
+                ctx.update.emit(settings);
+            })
+        };
+        html! {
+            <label class="flex justify-between items-center gap-4">
+                <span class="text-gray-600 dark:text-gray-400">{transform.label()}</span>
+                <input type="checkbox" checked={ctx.settings.transforms.contains(&transform)} {onchange} />
+            </label>
+        }
+    });
+
+    let current_theme = theme::resolve(&ctx.settings.theme, &ctx.settings.custom_themes);
+    let theme_is_custom = ctx.settings.custom_themes.iter().any(|t| t.name == current_theme.name);
+
+    let on_theme_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.theme = e.target_unchecked_into::<HtmlSelectElement>().value();
+            ctx.update.emit(settings);
+        })
+    };
+
+    // Custom themes start as a copy of the current one
+    let on_customise_theme = {
+        let ctx = ctx.clone();
+        let current_theme = current_theme.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut settings = ctx.settings.clone();
+            let mut copy = current_theme.clone();
+            copy.name = theme::unique_name(&format!("{} custom", current_theme.name), &settings.custom_themes);
+            settings.theme = copy.name.clone();
+            settings.custom_themes.push(copy);
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_delete_theme = {
+        let ctx = ctx.clone();
+        let name = current_theme.name.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut settings = ctx.settings.clone();
+            settings.custom_themes.retain(|t| t.name != name);
+            settings.theme = DEFAULT_THEME.to_string();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let theme_colour_rows = Slot::ALL.iter().map(|&slot| {
+        let onchange = {
+            let ctx = ctx.clone();
+            let name = current_theme.name.clone();
+            Callback::from(move |e: Event| {
+                let colour = e.target_unchecked_into::<HtmlInputElement>().value();
+                let mut settings = ctx.settings.clone();
+                if let Some(theme) = settings.custom_themes.iter_mut().find(|t| t.name == name) {
+                    theme.set_colour(slot, colour);
+                }
+                ctx.update.emit(settings);
+            })
+        };
+        html! {
+            <label class="flex justify-between items-center gap-4">
+                <span class="text-gray-600 dark:text-gray-400">{slot.label()}</span>
+                <input type="color" value={current_theme.colour(slot).to_string()} {onchange} />
+            </label>
+        }
+    });
+
+    let on_caret_style_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.caret.style = CaretStyle::ALL.into_iter().find(|s| s.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_caret_smooth_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.caret.smooth = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_caret_duration_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            if let Ok(ms) = e.target_unchecked_into::<HtmlInputElement>().value().parse::<u32>() {
+                let mut settings = ctx.settings.clone();
+                settings.caret.duration_ms = ms.min(MAX_DURATION_MS);
+                ctx.update.emit(settings);
+            }
+        })
+    };
+
+    let on_caret_blink_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.caret.blink = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_lines_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            if let Ok(lines) = e.target_unchecked_into::<HtmlSelectElement>().value().parse() {
+                let mut settings = ctx.settings.clone();
+                settings.view.lines = lines;
+                ctx.update.emit(settings);
+            }
+        })
+    };
+
+    let on_tape_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.view.tape = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_policy_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.display_policy = DisplayPolicy::ALL.into_iter().find(|p| p.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_highlight_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.word_feedback.highlight_current = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_underline_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.word_feedback.underline_errors = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_dim_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.word_feedback.dim_other_sentences = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_sound_enabled_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.sound.enabled = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_sound_profile_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.sound.profile = SwitchProfile::ALL.into_iter().find(|p| p.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_volume_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            if let Ok(percent) = e.target_unchecked_into::<HtmlInputElement>().value().parse::<f32>() {
+                let mut settings = ctx.settings.clone();
+                settings.sound.volume = (percent / 100.0).clamp(0.0, 1.0);
+                ctx.update.emit(settings);
+            }
+        })
+    };
+
+    let on_keyboard_enabled_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let mut settings = ctx.settings.clone();
+            settings.keyboard.enabled = e.target_unchecked_into::<HtmlInputElement>().checked();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_keyboard_layout_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.keyboard.layout = Layout::ALL.into_iter().find(|l| l.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    let on_keyboard_physical_change = {
+        let ctx = ctx.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<HtmlSelectElement>().value();
+            let mut settings = ctx.settings.clone();
+            settings.keyboard.physical = PhysicalLayout::ALL.into_iter().find(|p| p.label() == value).unwrap_or_default();
+            ctx.update.emit(settings);
+        })
+    };
+
+    // One checkbox per HUD setting
+    let hud_toggle = |label: &'static str, get: fn(&HudOptions) -> bool, set: fn(&mut HudOptions, bool)| {
+        let onchange = {
+            let ctx = ctx.clone();
+            Callback::from(move |e: Event| {
+                let mut settings = ctx.settings.clone();
+                set(&mut settings.hud, e.target_unchecked_into::<HtmlInputElement>().checked());
+                ctx.update.emit(settings);
+            })
+        };
+        html! {
+            <label class="flex justify-between items-center gap-4">
+                <span class="text-gray-600 dark:text-gray-400">{label}</span>
+                <input type="checkbox" checked={get(&ctx.settings.hud)} {onchange} />
+            </label>
+        }
+    };
+
+    let on_pack_change = {
+        let on_select_pack = props.on_select_pack.clone();
+        Callback::from(move |e: Event| {
+            on_select_pack.emit(e.target_unchecked_into::<HtmlSelectElement>().value());
+        })
+    };
+
+    let filter = &ctx.settings.quote_filter;
+    let mode = ctx.settings.mode;
+    let word_options = ctx.settings.word_options;
+    let caret = ctx.settings.caret;
+    let view = ctx.settings.view;
+    let feedback = ctx.settings.word_feedback;
+    let sound = ctx.settings.sound;
+    let keyboard = ctx.settings.keyboard;
+
+    html! {
+        <div class="mb-6 p-4 bg-gray-100 dark:bg-gray-800 rounded-lg text-sm">
+            <div class="font-bold mb-2">{"Mode"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Practise"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_mode_change}>
+                        { for Mode::ALL.iter().map(|m| html! {
+                            <option value={m.label()} selected={mode == *m}>{m.label()}</option>
+                        }) }
+                    </select>
+                </label>
+                if mode != Mode::Quote {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Word list"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_word_list_change}>
+                            { for WordList::ALL.iter().map(|l| html! {
+                                <option value={l.label()} selected={word_options.list == *l}>{l.label()}</option>
+                            }) }
+                        </select>
+                    </label>
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Punctuation"}</span>
+                        <input type="checkbox" checked={word_options.punctuation} onchange={on_punctuation_change} />
+                    </label>
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Numbers"}</span>
+                        <input type="checkbox" checked={word_options.numbers} onchange={on_numbers_change} />
+                    </label>
+                }
+            </div>
+            <div class="flex justify-between items-center mb-2">
+                <div class="font-bold">{"Theme"}</div>
+                <div class="flex gap-2">
+                    if theme_is_custom {
+                        <button class="text-xs text-gray-500 hover:underline" onclick={on_delete_theme}>{"Delete"}</button>
+                    }
+                    <button class="text-xs text-gray-500 hover:underline" onclick={on_customise_theme}>{"Customise"}</button>
+                </div>
+            </div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Colours"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_theme_change}>
+                        { for theme::Theme::builtin().iter().chain(&ctx.settings.custom_themes).map(|t| html! {
+                            <option value={t.name.clone()} selected={current_theme.name == t.name}>{&t.name}</option>
+                        }) }
+                    </select>
+                </label>
+                if theme_is_custom {
+                    { for theme_colour_rows }
+                }
+            </div>
+            <div class="font-bold mb-2">{"Text"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Tape (one scrolling line)"}</span>
+                    <input type="checkbox" checked={view.tape} onchange={on_tape_change} />
+                </label>
+                if !view.tape {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Visible lines"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_lines_change}>
+                            { for (1..=MAX_VISIBLE_LINES).map(|n| html! {
+                                <option value={n.to_string()} selected={view.visible_lines() == n}>{n}</option>
+                            }) }
+                        </select>
+                    </label>
+                }
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Mistakes"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_policy_change}>
+                        { for DisplayPolicy::ALL.iter().map(|p| html! {
+                            <option value={p.label()} selected={ctx.settings.display_policy == *p}>{p.label()}</option>
+                        }) }
+                    </select>
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Highlight the current word"}</span>
+                    <input type="checkbox" checked={feedback.highlight_current} onchange={on_highlight_change} />
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Underline words with mistakes"}</span>
+                    <input type="checkbox" checked={feedback.underline_errors} onchange={on_underline_change} />
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Dim all but the current sentence"}</span>
+                    <input type="checkbox" checked={feedback.dim_other_sentences} onchange={on_dim_change} />
+                </label>
+            </div>
+            <div class="font-bold mb-2">{"Live stats"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                {hud_toggle("Show while typing", |h| h.enabled, |h, on| h.enabled = on)}
+                if ctx.settings.hud.enabled {
+                    {hud_toggle("WPM", |h| h.wpm, |h, on| h.wpm = on)}
+                    {hud_toggle("Accuracy", |h| h.accuracy, |h, on| h.accuracy = on)}
+                    {hud_toggle("Errors", |h| h.errors, |h, on| h.errors = on)}
+                    {hud_toggle("Timer", |h| h.timer, |h, on| h.timer = on)}
+                }
+            </div>
+            <div class="font-bold mb-2">{"Keyboard"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Show next key and finger"}</span>
+                    <input type="checkbox" checked={keyboard.enabled} onchange={on_keyboard_enabled_change} />
+                </label>
+                if keyboard.enabled {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Layout"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_keyboard_layout_change}>
+                            { for Layout::ALL.iter().map(|l| html! {
+                                <option value={l.label()} selected={keyboard.layout == *l}>{l.label()}</option>
+                            }) }
+                        </select>
+                    </label>
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Physical layout"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_keyboard_physical_change}>
+                            { for PhysicalLayout::ALL.iter().map(|p| html! {
+                                <option value={p.label()} selected={keyboard.physical == *p}>{p.label()}</option>
+                            }) }
+                        </select>
+                    </label>
+                }
+            </div>
+            <div class="font-bold mb-2">{"Sound"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Keypress sounds"}</span>
+                    <input type="checkbox" checked={sound.enabled} onchange={on_sound_enabled_change} />
+                </label>
+                if sound.enabled {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Switch"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_sound_profile_change}>
+                            { for SwitchProfile::ALL.iter().map(|p| html! {
+                                <option value={p.label()} selected={sound.profile == *p}>{p.label()}</option>
+                            }) }
+                        </select>
+                    </label>
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Volume"}</span>
+                        <input type="range" min="0" max="100" step="5" class="w-56"
+                               value={((sound.volume * 100.0).round() as u32).to_string()}
+                               onchange={on_volume_change} />
+                    </label>
+                }
+            </div>
+            <div class="font-bold mb-2">{"Caret"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Style"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_caret_style_change}>
+                        { for CaretStyle::ALL.iter().map(|s| html! {
+                            <option value={s.label()} selected={caret.style == *s}>{s.label()}</option>
+                        }) }
+                    </select>
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Smooth movement"}</span>
+                    <input type="checkbox" checked={caret.smooth} onchange={on_caret_smooth_change} />
+                </label>
+                if caret.smooth {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Transition (ms)"}</span>
+                        <input type="number" min="0" max={MAX_DURATION_MS.to_string()} step="10"
+                               class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs"
+                               value={caret.duration_ms.to_string()}
+                               onchange={on_caret_duration_change} />
+                    </label>
+                }
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Blink when idle"}</span>
+                    <input type="checkbox" checked={caret.blink} onchange={on_caret_blink_change} />
+                </label>
+            </div>
+            <div class="font-bold mb-2">{"Transforms"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                { for transform_rows }
+            </div>
+            <div class="font-bold mb-2">{"Quotes"}</div>
+            <div class="flex flex-col gap-1 mb-4">
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Pack"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_pack_change}>
+                        { for quotes::PACK_SOURCES.iter().map(|p| html! {
+                            <option value={p.id} selected={props.pack_id == p.id}>{p.name}</option>
+                        }) }
+                    </select>
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Length"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_length_change}>
+                        <option value="" selected={filter.length.is_none()}>{"Any"}</option>
+                        { for LengthClass::ALL.iter().map(|l| html! {
+                            <option value={l.label()} selected={filter.length == Some(*l)}>{l.label()}</option>
+                        }) }
+                    </select>
+                </label>
+                <label class="flex justify-between items-center gap-4">
+                    <span class="text-gray-600 dark:text-gray-400">{"Difficulty"}</span>
+                    <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_difficulty_change}>
+                        <option value="" selected={filter.difficulty.is_none()}>{"Any"}</option>
+                        { for Difficulty::ALL.iter().map(|d| html! {
+                            <option value={d.label()} selected={filter.difficulty == Some(*d)}>{d.label()}</option>
+                        }) }
+                    </select>
+                </label>
+                if !tags.is_empty() {
+                    <label class="flex justify-between items-center gap-4">
+                        <span class="text-gray-600 dark:text-gray-400">{"Tag"}</span>
+                        <select class="w-56 px-2 py-1 rounded bg-gray-200 dark:bg-gray-700 text-xs" onchange={on_tag_change}>
+                            <option value="" selected={filter.tag.is_none()}>{"Any"}</option>
+                            { for tags.iter().map(|t| html! {
+                                <option value={t.clone()} selected={filter.tag.as_ref() == Some(t)}>{t}</option>
+                            }) }
+                        </select>
+                    </label>
+                }
+            </div>
+            <div class="flex justify-between items-center mb-2">
+                <div class="font-bold">{"Key bindings"}</div>
+                <button class="text-xs text-gray-500 hover:underline" onclick={on_reset_bindings}>{"Reset to defaults"}</button>
+            </div>
+            <div class="flex flex-col gap-1">
+                {key_binding_rows}
+            </div>
+            if let Some(err) = &*binding_error {
+                <div class="mt-2 text-red-500 dark:text-red-400">{err}</div>
+            }
+            <div class="mt-2 text-xs text-gray-500">{"Separate alternatives with commas, e.g. Ctrl+W, Alt+Backspace"}</div>
+        </div>
+    }
+}
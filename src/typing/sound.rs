@@ -0,0 +1,304 @@
+// Synthesised keyboard sounds.
+//
+// Each sound is a short tone that drops in pitch, for the body of the switch, plus a
+// filtered noise burst for the attack. Samples are rendered here in Rust, once per sound,
+// and handed to Web Audio as buffers, so a keystroke only has to start a buffer playing.
+// Rendering is plain arithmetic, so the sounds are tested by rendering them offline.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use web_sys::{AudioBuffer, AudioContext, AudioContextState, GainNode};
+
+use super::words::Rng;
+
+// The noise is the same every time, so a sound doesn't change from one render to the next
+const NOISE_SEED: u64 = 0x0074_686f_636b;
+// Faded out over the last few milliseconds so the buffer doesn't end on a click
+const FADE_OUT_S: f32 = 0.003;
+// Playback speed varies by up to this much, so fast typing doesn't sound mechanical
+const PITCH_JITTER: f32 = 0.04;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchProfile {
+    // Deep and muffled, like a lubed linear switch
+    #[default]
+    Thock,
+    // Higher and sharper
+    Clack,
+    // Short and bright, like a clicky switch
+    Click,
+}
+
+impl SwitchProfile {
+    pub const ALL: [SwitchProfile; 3] = [SwitchProfile::Thock, SwitchProfile::Clack, SwitchProfile::Click];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SwitchProfile::Thock => "Thock",
+            SwitchProfile::Clack => "Clack",
+            SwitchProfile::Click => "Click",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Key(SwitchProfile),
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundOptions {
+    pub enabled: bool,
+    pub profile: SwitchProfile,
+    // 0 to 1
+    pub volume: f32,
+}
+
+impl Default for SoundOptions {
+    fn default() -> Self {
+        SoundOptions { enabled: false, profile: SwitchProfile::Thock, volume: 0.5 }
+    }
+}
+
+// Synthesis parameters, times in seconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Voice {
+    pub duration: f32,
+    // Pitch of the body at the start, falling towards `tone_end_hz`
+    pub tone_hz: f32,
+    pub tone_end_hz: f32,
+    pub tone_level: f32,
+    pub tone_decay: f32,
+    pub noise_level: f32,
+    pub noise_decay: f32,
+    // Low-pass coefficient for the noise, 0 to 1, higher is brighter
+    pub brightness: f32,
+}
+
+impl Sound {
+    pub fn voice(&self) -> Voice {
+        match self {
+            Sound::Key(SwitchProfile::Thock) => Voice {
+                duration: 0.09,
+                tone_hz: 220.0,
+                tone_end_hz: 110.0,
+                tone_level: 0.5,
+                tone_decay: 0.03,
+                noise_level: 0.35,
+                noise_decay: 0.008,
+                brightness: 0.15,
+            },
+            Sound::Key(SwitchProfile::Clack) => Voice {
+                duration: 0.07,
+                tone_hz: 520.0,
+                tone_end_hz: 320.0,
+                tone_level: 0.3,
+                tone_decay: 0.02,
+                noise_level: 0.5,
+                noise_decay: 0.01,
+                brightness: 0.45,
+            },
+            Sound::Key(SwitchProfile::Click) => Voice {
+                duration: 0.05,
+                tone_hz: 2400.0,
+                tone_end_hz: 1800.0,
+                tone_level: 0.25,
+                tone_decay: 0.006,
+                noise_level: 0.6,
+                noise_decay: 0.004,
+                brightness: 0.9,
+            },
+            // A low, longer buzz that stands apart from every switch
+            Sound::Error => Voice {
+                duration: 0.15,
+                tone_hz: 160.0,
+                tone_end_hz: 140.0,
+                tone_level: 0.6,
+                tone_decay: 0.06,
+                noise_level: 0.1,
+                noise_decay: 0.005,
+                brightness: 0.2,
+            },
+        }
+    }
+}
+
+// Mono samples between -1 and 1
+pub fn render(voice: &Voice, sample_rate: f32) -> Vec<f32> {
+    let len = (voice.duration * sample_rate).round() as usize;
+    let fade_len = (FADE_OUT_S * sample_rate) as usize;
+    let mut rng = Rng::new(NOISE_SEED);
+    let mut phase = 0.0f32;
+    let mut noise = 0.0f32;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / sample_rate;
+            let tone_envelope = (-t / voice.tone_decay).exp();
+            let hz = voice.tone_end_hz + (voice.tone_hz - voice.tone_end_hz) * tone_envelope;
+            phase = (phase + TAU * hz / sample_rate) % TAU;
+            let tone = phase.sin() * voice.tone_level * tone_envelope;
+
+            let white = rng.next_f64() as f32 * 2.0 - 1.0;
+            noise += voice.brightness * (white - noise);
+            let burst = noise * voice.noise_level * (-t / voice.noise_decay).exp();
+
+            let fade = ((len - i) as f32 / fade_len.max(1) as f32).min(1.0);
+            ((tone + burst) * fade).clamp(-1.0, 1.0)
+        })
+        .collect()
+}
+
+// Plays the rendered sounds. Browsers only allow audio once the page has had a user
+// gesture, so this is created on the first keystroke rather than up front.
+pub struct Player {
+    context: AudioContext,
+    gain: GainNode,
+    buffers: HashMap<Sound, AudioBuffer>,
+}
+
+impl Player {
+    fn new() -> Result<Self, JsValue> {
+        let context = AudioContext::new()?;
+        let gain = context.create_gain()?;
+        gain.connect_with_audio_node(&context.destination())?;
+        Ok(Player { context, gain, buffers: HashMap::new() })
+    }
+
+    fn buffer(&mut self, sound: Sound) -> Result<AudioBuffer, JsValue> {
+        if let Some(buffer) = self.buffers.get(&sound) {
+            return Ok(buffer.clone());
+        }
+        let sample_rate = self.context.sample_rate();
+        let samples = render(&sound.voice(), sample_rate);
+        let buffer = self.context.create_buffer(1, samples.len().max(1) as u32, sample_rate)?;
+        buffer.copy_to_channel(&samples, 0)?;
+        self.buffers.insert(sound, buffer.clone());
+        Ok(buffer)
+    }
+
+    fn play(&mut self, sound: Sound, volume: f32) -> Result<(), JsValue> {
+        if self.context.state() == AudioContextState::Suspended {
+            let _ = self.context.resume()?;
+        }
+        let buffer = self.buffer(sound)?;
+        self.gain.gain().set_value(volume.clamp(0.0, 1.0));
+        let source = self.context.create_buffer_source()?;
+        source.set_buffer(Some(&buffer));
+        let jitter = (js_sys::Math::random() as f32 * 2.0 - 1.0) * PITCH_JITTER;
+        source.playback_rate().set_value(1.0 + jitter);
+        source.connect_with_audio_node(&self.gain)?;
+        source.start()
+    }
+}
+
+// Plays `sound` if sounds are on, setting up `player` the first time
+pub fn play(player: &RefCell<Option<Player>>, options: &SoundOptions, sound: Sound) {
+    if !options.enabled || options.volume <= 0.0 {
+        return;
+    }
+    let mut player = player.borrow_mut();
+    if player.is_none() {
+        match Player::new() {
+            Ok(created) => *player = Some(created),
+            Err(e) => {
+                log::warn!("failed to start audio: {:?}", e);
+                return;
+            }
+        }
+    }
+    if let Some(player) = player.as_mut() {
+        if let Err(e) = player.play(sound, options.volume) {
+            log::warn!("failed to play sound: {:?}", e);
+        }
+    }
+}
+
+// Closes the audio context, if one was started. Browsers only allow a few at a time, so
+// this is called when the typing view goes away.
+pub fn stop(player: &RefCell<Option<Player>>) {
+    if let Some(player) = player.borrow_mut().take() {
+        if let Err(e) = player.context.close() {
+            log::warn!("failed to close audio: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    fn all_sounds() -> Vec<Sound> {
+        SwitchProfile::ALL.iter().map(|&p| Sound::Key(p)).chain([Sound::Error]).collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    // Sign changes per second over the first `seconds`, a rough measure of brightness
+    fn zero_crossing_rate(samples: &[f32], seconds: f32) -> f32 {
+        let n = (seconds * SAMPLE_RATE) as usize;
+        let crossings = samples[..n].windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        crossings as f32 / seconds
+    }
+
+    #[test]
+    fn test_sounds_render_within_range() {
+        for sound in all_sounds() {
+            let voice = sound.voice();
+            let samples = render(&voice, SAMPLE_RATE);
+            assert_eq!(samples.len(), (voice.duration * SAMPLE_RATE).round() as usize, "{:?}", sound);
+            assert!(samples.iter().all(|s| s.is_finite() && s.abs() <= 1.0), "{:?}", sound);
+            let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+            assert!(peak > 0.1, "{:?} is too quiet: {}", sound, peak);
+            assert_eq!(samples, render(&voice, SAMPLE_RATE), "{:?} isn't deterministic", sound);
+        }
+    }
+
+    #[test]
+    fn test_sounds_decay() {
+        for sound in all_sounds() {
+            let samples = render(&sound.voice(), SAMPLE_RATE);
+            let tenth = samples.len() / 10;
+            let start = rms(&samples[..tenth]);
+            let end = rms(&samples[samples.len() - tenth..]);
+            assert!(end < start * 0.1, "{:?} doesn't decay: {} -> {}", sound, start, end);
+            assert!(samples.last().unwrap().abs() < 1e-3, "{:?} ends on a click", sound);
+        }
+    }
+
+    #[test]
+    fn test_profiles_get_brighter() {
+        let rates: Vec<f32> = SwitchProfile::ALL
+            .iter()
+            .map(|&p| zero_crossing_rate(&render(&Sound::Key(p).voice(), SAMPLE_RATE), 0.02))
+            .collect();
+        assert!(rates.windows(2).all(|w| w[0] < w[1]), "thock, clack, click: {:?}", rates);
+    }
+
+    #[test]
+    fn test_error_sound_is_distinct() {
+        let error = render(&Sound::Error.voice(), SAMPLE_RATE);
+        for profile in SwitchProfile::ALL {
+            let key = render(&Sound::Key(profile).voice(), SAMPLE_RATE);
+            assert!(error.len() > key.len());
+            assert!(rms(&error) > rms(&key), "error should be louder than {:?}", profile);
+        }
+    }
+
+    #[test]
+    fn test_other_sample_rates() {
+        let voice = Sound::Key(SwitchProfile::Thock).voice();
+        assert_eq!(render(&voice, 48_000.0).len(), (voice.duration * 48_000.0).round() as usize);
+        assert!(render(&Voice { duration: 0.0, ..voice }, SAMPLE_RATE).is_empty());
+    }
+}
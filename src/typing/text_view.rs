@@ -0,0 +1,259 @@
+// The text being typed, one component per word.
+//
+// The status of every character is worked out once per keystroke, and each word only
+// re-renders when its own characters or the cursor in it change, so a keystroke
+// updates one or two words instead of every character on screen.
+//
+// Word-level feedback (the active word, finished words with mistakes, the current
+// sentence) is worked out from the same character states, so it always agrees with
+// the marks on the characters.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use super::matching::EditOp;
+use super::sections::SENTENCE_ENDS;
+use super::theme::{use_theme, with_alpha};
+
+// Marks the character the cursor is on, for the cursor overlay to find
+pub const CURSOR_ATTR: &str = "data-cursor";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CharState {
+    pub ch: char,
+    // Reached by the input
+    pub done: bool,
+    // Mistyped or skipped
+    pub error: bool,
+    // The wrong character typed in its place, None if it was skipped
+    pub typed: Option<char>,
+    // Extra characters typed before it
+    pub inserted: Vec<char>,
+}
+
+// One entry per character of the quote
+pub fn char_states(quote: &str, alignment: &[(EditOp, Option<char>, Option<char>)]) -> Vec<CharState> {
+    let mut states: Vec<CharState> = quote.chars().map(|ch| CharState { ch, ..Default::default() }).collect();
+    let mut pos = 0;
+    for (op, _, input_char) in alignment {
+        match op {
+            EditOp::Insert => {
+                if let (Some(state), Some(c)) = (states.get_mut(pos), input_char) {
+                    state.inserted.push(*c);
+                }
+            }
+            EditOp::Match | EditOp::Substitute => {
+                if let Some(state) = states.get_mut(pos) {
+                    state.done = true;
+                    if *op == EditOp::Substitute {
+                        state.error = true;
+                        state.typed = *input_char;
+                    }
+                }
+                pos += 1;
+            }
+        }
+    }
+    states
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WordFeedback {
+    pub highlight_current: bool,
+    // Underline finished words that had mistakes in them
+    pub underline_errors: bool,
+    pub dim_other_sentences: bool,
+}
+
+// How much of the alignment is shown while typing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayPolicy {
+    #[default]
+    Full,
+    // Typed characters all look the same and extra characters aren't shown, mistakes
+    // are only revealed on the results
+    Blind,
+}
+
+impl DisplayPolicy {
+    pub const ALL: [DisplayPolicy; 2] = [DisplayPolicy::Full, DisplayPolicy::Blind];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayPolicy::Full => "Show mistakes",
+            DisplayPolicy::Blind => "Blind (hide mistakes)",
+        }
+    }
+
+    pub fn shows_errors(&self) -> bool {
+        *self == DisplayPolicy::Full
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordState {
+    // Quote positions of the word and the space after it
+    pub span: Range<usize>,
+    // Index of the sentence the word is in
+    pub sentence: usize,
+    // Mistyped, skipped or extra characters
+    pub has_error: bool,
+}
+
+// Words as they come out of the alignment, in order
+pub fn word_states(states: &[CharState]) -> Vec<WordState> {
+    let mut words = Vec::new();
+    let mut sentence = 0;
+    let mut start = 0;
+    for (i, state) in states.iter().enumerate() {
+        let last = i + 1 == states.len();
+        if state.ch != ' ' && !last {
+            continue;
+        }
+        let span = start..i + 1;
+        let word = &states[span.clone()];
+        let has_error = word.iter().any(|s| s.error || !s.inserted.is_empty());
+        let ends_sentence = word.iter().rev().find(|s| s.ch != ' ').is_some_and(|s| SENTENCE_ENDS.contains(&s.ch));
+        if word.iter().any(|s| s.ch != ' ') {
+            words.push(WordState { span, sentence, has_error });
+        }
+        if ends_sentence {
+            sentence += 1;
+        }
+        start = i + 1;
+    }
+    words
+}
+
+// The words overlapping quote positions `range`, e.g. those on a line
+pub fn words_in(words: &[WordState], range: Range<usize>) -> &[WordState] {
+    let first = words.partition_point(|w| w.span.end <= range.start);
+    let last = words.partition_point(|w| w.span.start < range.end);
+    &words[first..last.max(first)]
+}
+
+// The word holding quote position `pos`, the last word once the text is done
+pub fn word_containing(words: &[WordState], pos: usize) -> Option<&WordState> {
+    words.iter().find(|w| w.span.contains(&pos)).or(words.last())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WordProps {
+    // The word's characters, followed by the space after it if there is one
+    pub chars: Vec<CharState>,
+    // Offset of the cursor in `chars`, if it is in this word
+    pub cursor: Option<usize>,
+    #[prop_or_default]
+    pub highlight: bool,
+    #[prop_or_default]
+    pub underline: bool,
+    #[prop_or_default]
+    pub dimmed: bool,
+    #[prop_or_default]
+    pub policy: DisplayPolicy,
+}
+
+#[function_component]
+pub fn Word(props: &WordProps) -> Html {
+    let theme = use_theme();
+    let shows_errors = props.policy.shows_errors();
+    let mut word_style = String::new();
+    if props.highlight {
+        word_style.push_str(&format!("background-color: {}; border-radius: 0.25rem;", with_alpha(&theme.caret, 0.15)));
+    }
+    if props.dimmed {
+        word_style.push_str(" opacity: 0.35;");
+    }
+    html! {
+        <span style={word_style}>
+        { for props.chars.iter().enumerate().map(|(i, state)| {
+            let mut style = match (state.done, state.error && shows_errors) {
+                (true, true) => format!("color: {}; background-color: {};", theme.error, with_alpha(&theme.error, 0.2)),
+                (true, false) => format!("color: {};", theme.correct),
+                (false, _) => format!("color: {};", theme.pending),
+            };
+            if props.underline && shows_errors && state.ch != ' ' {
+                style.push_str(&format!(" text-decoration: underline; text-decoration-color: {};", theme.error));
+            }
+            let cursor = (props.cursor == Some(i)).then_some("");
+            let display = if state.ch == ' ' { '\u{00A0}' } else { state.ch };
+            html! {
+                <span class="relative inline">
+                    if shows_errors && !state.inserted.is_empty() {
+                        <span class="absolute left-0 flex flex-col-reverse items-center pointer-events-none z-20" style="bottom: 100%; line-height: 1;">
+                            { for state.inserted.iter().map(|&c| {
+                                let display_char = if c == ' ' { '\u{2423}' } else { c };
+                                html! { <span class="text-sm font-bold" style={format!("color: {};", theme.inserted)}>{display_char}</span> }
+                            }) }
+                        </span>
+                    }
+                    <span {style} data-cursor={cursor}>{display}</span>
+                    if let Some(typed) = state.typed.filter(|_| shows_errors) {
+                        <span class="absolute text-xs" style={format!("top: 100%; left: 0; line-height: 1; color: {};", with_alpha(&theme.error, 0.7))}>{typed}</span>
+                    }
+                </span>
+            }
+        }) }
+        </span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::matching::align_incremental;
+
+    fn words(quote: &str, input: &str) -> Vec<WordState> {
+        word_states(&char_states(quote, &align_incremental(quote, input)))
+    }
+
+    #[test]
+    fn test_char_states() {
+        let states = char_states("abc def", &align_incremental("abc def", "abc"));
+        assert_eq!(states.len(), 7);
+        assert!(states[..3].iter().all(|s| s.done && !s.error));
+        assert!(states[3..].iter().all(|s| !s.done));
+    }
+
+    #[test]
+    fn test_char_states_records_mistakes() {
+        let states = char_states("abc", &align_incremental("abc", "axc"));
+        assert_eq!(states[1].typed, Some('x'));
+        assert!(states[1].error && !states[0].error && !states[2].error);
+        assert!(states.iter().all(|s| s.done));
+
+        let states = char_states("ab", &align_incremental("ab", "azb"));
+        let inserted: Vec<char> = states.iter().flat_map(|s| s.inserted.clone()).collect();
+        assert_eq!(inserted, vec!['z']);
+        assert!(states.iter().all(|s| s.typed.is_none()));
+    }
+
+    #[test]
+    fn test_word_states() {
+        let found = words("One two. Three? Four", "");
+        let spans: Vec<Range<usize>> = found.iter().map(|w| w.span.clone()).collect();
+        assert_eq!(spans, vec![0..4, 4..9, 9..16, 16..20]);
+        let sentences: Vec<usize> = found.iter().map(|w| w.sentence).collect();
+        assert_eq!(sentences, vec![0, 0, 1, 2]);
+        assert!(found.iter().all(|w| !w.has_error));
+
+        assert_eq!(words_in(&found, 0..9), &found[..2]);
+        assert_eq!(words_in(&found, 8..10), &found[1..3]);
+        assert_eq!(words_in(&found, 0..100), &found[..]);
+        assert!(words_in(&found, 30..40).is_empty());
+        assert_eq!(word_containing(&found, 8).map(|w| w.span.start), Some(4));
+        assert_eq!(word_containing(&found, 20).map(|w| w.span.start), Some(16));
+        assert!(word_states(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_word_errors() {
+        let found = words("abc def ghi", "abx dzef");
+        let errors: Vec<bool> = found.iter().map(|w| w.has_error).collect();
+        assert_eq!(errors, vec![true, true, false]);
+    }
+}
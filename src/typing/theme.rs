@@ -0,0 +1,277 @@
+// Colour themes for the typing view and the results chart, shared through a yew context.
+//
+// Colours are applied as inline styles rather than Tailwind classes, so user-defined
+// themes can use any colour. They are kept as "#rrggbb" so they round-trip through
+// `<input type="color">`.
+
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use super::settings::use_settings;
+
+pub const DEFAULT_THEME: &str = "Default";
+
+// Missing colours in a stored theme are taken from the default one
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    pub background: String,
+    // Typed correctly
+    pub correct: String,
+    // Not reached yet
+    pub pending: String,
+    pub error: String,
+    // Extra characters typed before a character
+    pub inserted: String,
+    pub caret: String,
+    // Progress bar
+    pub accent: String,
+    pub chart_wpm: String,
+    pub chart_cpm: String,
+    pub chart_grid: String,
+    pub chart_error: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Background,
+    Correct,
+    Pending,
+    Error,
+    Inserted,
+    Caret,
+    Accent,
+    ChartWpm,
+    ChartCpm,
+    ChartGrid,
+    ChartError,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 11] = [
+        Slot::Background,
+        Slot::Correct,
+        Slot::Pending,
+        Slot::Error,
+        Slot::Inserted,
+        Slot::Caret,
+        Slot::Accent,
+        Slot::ChartWpm,
+        Slot::ChartCpm,
+        Slot::ChartGrid,
+        Slot::ChartError,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Slot::Background => "Background",
+            Slot::Correct => "Correct",
+            Slot::Pending => "Not yet typed",
+            Slot::Error => "Error",
+            Slot::Inserted => "Extra characters",
+            Slot::Caret => "Caret",
+            Slot::Accent => "Progress bar",
+            Slot::ChartWpm => "Chart WPM",
+            Slot::ChartCpm => "Chart CPM",
+            Slot::ChartGrid => "Chart grid",
+            Slot::ChartError => "Chart errors",
+        }
+    }
+}
+
+impl Theme {
+    // Colours in the order of `Slot::ALL`
+    fn new(name: &str, colours: [&str; 11]) -> Self {
+        let mut theme = Theme {
+            name: name.to_string(),
+            background: String::new(),
+            correct: String::new(),
+            pending: String::new(),
+            error: String::new(),
+            inserted: String::new(),
+            caret: String::new(),
+            accent: String::new(),
+            chart_wpm: String::new(),
+            chart_cpm: String::new(),
+            chart_grid: String::new(),
+            chart_error: String::new(),
+        };
+        for (slot, colour) in Slot::ALL.into_iter().zip(colours) {
+            theme.set_colour(slot, colour.to_string());
+        }
+        theme
+    }
+
+    pub fn builtin() -> Vec<Theme> {
+        vec![
+            Theme::new(DEFAULT_THEME, [
+                "#1f2937", "#ffffff", "#6b7280", "#f87171", "#f87171", "#facc15",
+                "#3b82f6", "#3b82f6", "#06b6d4", "#4b5563", "#ef4444",
+            ]),
+            Theme::new("Light", [
+                "#f3f4f6", "#111827", "#9ca3af", "#dc2626", "#dc2626", "#2563eb",
+                "#2563eb", "#2563eb", "#0891b2", "#d1d5db", "#dc2626",
+            ]),
+            Theme::new("Nord", [
+                "#2e3440", "#eceff4", "#4c566a", "#bf616a", "#d08770", "#88c0d0",
+                "#88c0d0", "#81a1c1", "#8fbcbb", "#434c5e", "#bf616a",
+            ]),
+            Theme::new("Solarized Dark", [
+                "#002b36", "#93a1a1", "#586e75", "#dc322f", "#cb4b16", "#b58900",
+                "#268bd2", "#268bd2", "#2aa198", "#073642", "#dc322f",
+            ]),
+            Theme::new("Dracula", [
+                "#282a36", "#f8f8f2", "#6272a4", "#ff5555", "#ffb86c", "#f1fa8c",
+                "#bd93f9", "#bd93f9", "#8be9fd", "#44475a", "#ff5555",
+            ]),
+        ]
+    }
+
+    pub fn colour(&self, slot: Slot) -> &str {
+        match slot {
+            Slot::Background => &self.background,
+            Slot::Correct => &self.correct,
+            Slot::Pending => &self.pending,
+            Slot::Error => &self.error,
+            Slot::Inserted => &self.inserted,
+            Slot::Caret => &self.caret,
+            Slot::Accent => &self.accent,
+            Slot::ChartWpm => &self.chart_wpm,
+            Slot::ChartCpm => &self.chart_cpm,
+            Slot::ChartGrid => &self.chart_grid,
+            Slot::ChartError => &self.chart_error,
+        }
+    }
+
+    pub fn set_colour(&mut self, slot: Slot, colour: String) {
+        let field = match slot {
+            Slot::Background => &mut self.background,
+            Slot::Correct => &mut self.correct,
+            Slot::Pending => &mut self.pending,
+            Slot::Error => &mut self.error,
+            Slot::Inserted => &mut self.inserted,
+            Slot::Caret => &mut self.caret,
+            Slot::Accent => &mut self.accent,
+            Slot::ChartWpm => &mut self.chart_wpm,
+            Slot::ChartCpm => &mut self.chart_cpm,
+            Slot::ChartGrid => &mut self.chart_grid,
+            Slot::ChartError => &mut self.chart_error,
+        };
+        *field = colour;
+    }
+
+    // Stored themes are edited by hand sometimes, so check before using one
+    pub fn is_valid(&self) -> bool {
+        !self.name.trim().is_empty() && Slot::ALL.iter().all(|&slot| is_hex_colour(self.colour(slot)))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::builtin().remove(0)
+    }
+}
+
+pub fn is_hex_colour(colour: &str) -> bool {
+    colour.len() == 7 && colour.starts_with('#') && colour[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// "#rrggbb" with an opacity between 0 and 1, as "#rrggbbaa"
+pub fn with_alpha(colour: &str, alpha: f64) -> String {
+    format!("{}{:02x}", colour, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+// Built-in themes come first, so a custom theme can't take over a built-in name
+pub fn resolve(name: &str, custom: &[Theme]) -> Theme {
+    Theme::builtin()
+        .into_iter()
+        .chain(custom.iter().filter(|t| t.is_valid()).cloned())
+        .find(|t| t.name == name)
+        .unwrap_or_default()
+}
+
+// `base`, or `base 2`, `base 3`... whichever isn't taken yet
+pub fn unique_name(base: &str, custom: &[Theme]) -> String {
+    let taken = |name: &str| Theme::builtin().iter().chain(custom).any(|t| t.name == name);
+    if !taken(base) {
+        return base.to_string();
+    }
+    (2..).map(|n| format!("{} {}", base, n)).find(|name| !taken(name)).unwrap()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ThemeProviderProps {
+    pub children: Html,
+}
+
+// Provides the theme picked in settings
+#[function_component]
+pub fn ThemeProvider(props: &ThemeProviderProps) -> Html {
+    let settings = use_settings().settings;
+    let theme = use_memo((settings.theme, settings.custom_themes), |(name, custom)| resolve(name, custom));
+
+    html! {
+        <ContextProvider<Theme> context={(*theme).clone()}>
+            {props.children.clone()}
+        </ContextProvider<Theme>>
+    }
+}
+
+#[hook]
+pub fn use_theme() -> Theme {
+    use_context::<Theme>().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_themes_are_valid() {
+        let themes = Theme::builtin();
+        assert_eq!(themes[0].name, DEFAULT_THEME);
+        assert!(themes.iter().all(Theme::is_valid));
+        let mut names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), themes.len());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut mine = resolve("Nord", &[]);
+        assert_eq!(mine.name, "Nord");
+        mine.name = "Mine".to_string();
+        mine.set_colour(Slot::Caret, "#123456".to_string());
+        assert_eq!(resolve("Mine", &[mine.clone()]).caret, "#123456");
+        assert_eq!(resolve("Missing", &[mine.clone()]).name, DEFAULT_THEME);
+
+        // Invalid and shadowing custom themes are ignored
+        let mut broken = mine.clone();
+        broken.set_colour(Slot::Error, "red".to_string());
+        assert_eq!(resolve("Mine", &[broken]).name, DEFAULT_THEME);
+        let mut shadow = mine;
+        shadow.name = "Nord".to_string();
+        assert_eq!(resolve("Nord", &[shadow]).caret, "#88c0d0");
+    }
+
+    #[test]
+    fn test_colours() {
+        assert!(is_hex_colour("#a0B1c2"));
+        assert!(!is_hex_colour("#abc"));
+        assert!(!is_hex_colour("a0b1c2f"));
+        assert_eq!(with_alpha("#ff0000", 0.3), "#ff00004d");
+        assert_eq!(with_alpha("#ff0000", 2.0), "#ff0000ff");
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let mut custom = vec![Theme { name: "Nord custom".to_string(), ..Theme::default() }];
+        assert_eq!(unique_name("Mine", &custom), "Mine");
+        assert_eq!(unique_name("Nord", &custom), "Nord 2");
+        assert_eq!(unique_name("Nord custom", &custom), "Nord custom 2");
+        custom.push(Theme { name: "Nord custom 2".to_string(), ..Theme::default() });
+        assert_eq!(unique_name("Nord custom", &custom), "Nord custom 3");
+    }
+}
@@ -0,0 +1,239 @@
+// Transformations applied to a text before it is played: stripping punctuation,
+// lowercasing, adding numbers, random capitals, or swapping words for symbol-heavy
+// variants. Each one is a `Transformer`; the active ones run in the order of
+// `Transform::ALL`, whatever order they were picked in.
+//
+// Random choices are seeded from the quote id, so a quote always comes out the same
+// under the same transforms and results stay comparable.
+
+use serde::{Deserialize, Serialize};
+
+use super::quotes::{content_id, Quote};
+use super::words::{capitalise, random_number, Rng};
+
+const NUMBER_CHANCE: f64 = 0.15;
+const CAPITALISE_CHANCE: f64 = 0.25;
+const SHOUT_CHANCE: f64 = 0.05;
+const WRAP_CHANCE: f64 = 0.15;
+
+// Words with a symbol that means the same thing
+const SYMBOL_WORDS: [(&str, &str); 10] = [
+    ("and", "&"),
+    ("at", "@"),
+    ("plus", "+"),
+    ("minus", "-"),
+    ("equals", "="),
+    ("percent", "%"),
+    ("number", "#"),
+    ("dollars", "$"),
+    ("times", "*"),
+    ("or", "|"),
+];
+const WRAPPERS: [(&str, &str); 8] = [
+    ("(", ")"),
+    ("[", "]"),
+    ("{", "}"),
+    ("<", ">"),
+    ("\"", "\""),
+    ("'", "'"),
+    ("`", "`"),
+    ("_", "_"),
+];
+
+pub trait Transformer {
+    fn apply(&self, text: &str, rng: &mut Rng) -> String;
+}
+
+// Rebuilds the text word by word, dropping words that end up empty
+fn map_words(text: &str, mut f: impl FnMut(&str) -> String) -> String {
+    text.split_whitespace().map(&mut f).filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+pub struct StripPunctuation;
+
+impl Transformer for StripPunctuation {
+    fn apply(&self, text: &str, _rng: &mut Rng) -> String {
+        map_words(text, |word| word.chars().filter(|c| c.is_alphanumeric()).collect())
+    }
+}
+
+pub struct Lowercase;
+
+impl Transformer for Lowercase {
+    fn apply(&self, text: &str, _rng: &mut Rng) -> String {
+        text.to_lowercase()
+    }
+}
+
+pub struct RandomCaps;
+
+impl Transformer for RandomCaps {
+    fn apply(&self, text: &str, rng: &mut Rng) -> String {
+        map_words(text, |word| {
+            if rng.chance(SHOUT_CHANCE) {
+                word.to_uppercase()
+            } else if rng.chance(CAPITALISE_CHANCE) {
+                capitalise(word)
+            } else {
+                word.to_string()
+            }
+        })
+    }
+}
+
+pub struct InjectNumbers;
+
+impl Transformer for InjectNumbers {
+    fn apply(&self, text: &str, rng: &mut Rng) -> String {
+        map_words(text, |word| {
+            if rng.chance(NUMBER_CHANCE) {
+                format!("{} {}", word, random_number(rng))
+            } else {
+                word.to_string()
+            }
+        })
+    }
+}
+
+pub struct SymbolVariants;
+
+impl Transformer for SymbolVariants {
+    fn apply(&self, text: &str, rng: &mut Rng) -> String {
+        map_words(text, |word| {
+            let symbol = SYMBOL_WORDS.iter().find(|(w, _)| word.eq_ignore_ascii_case(w));
+            if let Some((_, symbol)) = symbol {
+                symbol.to_string()
+            } else if rng.chance(WRAP_CHANCE) {
+                let (open, close) = rng.pick(&WRAPPERS);
+                format!("{}{}{}", open, word, close)
+            } else {
+                word.to_string()
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    StripPunctuation,
+    Lowercase,
+    RandomCaps,
+    InjectNumbers,
+    SymbolVariants,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 5] = [
+        Transform::StripPunctuation,
+        Transform::Lowercase,
+        Transform::RandomCaps,
+        Transform::InjectNumbers,
+        Transform::SymbolVariants,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Transform::StripPunctuation => "No punctuation",
+            Transform::Lowercase => "Lowercase",
+            Transform::RandomCaps => "Random capitals",
+            Transform::InjectNumbers => "Numbers",
+            Transform::SymbolVariants => "Symbols",
+        }
+    }
+
+    pub fn transformer(&self) -> &'static dyn Transformer {
+        match self {
+            Transform::StripPunctuation => &StripPunctuation,
+            Transform::Lowercase => &Lowercase,
+            Transform::RandomCaps => &RandomCaps,
+            Transform::InjectNumbers => &InjectNumbers,
+            Transform::SymbolVariants => &SymbolVariants,
+        }
+    }
+}
+
+// Runs the active transforms over the quote's text. The id is kept, so the quote can
+// still be linked.
+pub fn apply(active: &[Transform], quote: &Quote) -> Quote {
+    if active.is_empty() {
+        return quote.clone();
+    }
+    let mut rng = Rng::new(u64::from_str_radix(&content_id(&quote.id), 16).unwrap_or_default());
+    let mut text = quote.text.clone();
+    for transform in Transform::ALL.iter().filter(|t| active.contains(t)) {
+        text = transform.transformer().apply(&text, &mut rng);
+    }
+    Quote { text, ..quote.clone() }
+}
+
+// e.g. "Lowercase, Numbers", in pipeline order
+pub fn describe(active: &[Transform]) -> String {
+    Transform::ALL
+        .iter()
+        .filter(|t| active.contains(t))
+        .map(|t| t.label())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Then, at last, she said: \"It's ten percent more, and that's that!\"";
+
+    fn run(transform: Transform) -> String {
+        transform.transformer().apply(TEXT, &mut Rng::new(7))
+    }
+
+    #[test]
+    fn test_strip_punctuation() {
+        assert_eq!(run(Transform::StripPunctuation), "Then at last she said Its ten percent more and thats that");
+        assert_eq!(StripPunctuation.apply("a - b", &mut Rng::new(0)), "a b");
+    }
+
+    #[test]
+    fn test_lowercase() {
+        assert_eq!(run(Transform::Lowercase), TEXT.to_lowercase());
+    }
+
+    #[test]
+    fn test_random_caps_only_changes_case() {
+        let text = "the quick brown fox jumps over the lazy dog ".repeat(10);
+        let capped = RandomCaps.apply(&text, &mut Rng::new(3));
+        assert_ne!(capped, text.trim());
+        assert_eq!(capped.to_lowercase(), text.trim());
+    }
+
+    #[test]
+    fn test_inject_numbers_keeps_the_words() {
+        let text = "one two three four five six seven eight nine ten ".repeat(5);
+        let out = InjectNumbers.apply(&text, &mut Rng::new(5));
+        assert!(out.split(' ').any(|w| w.chars().all(|c| c.is_ascii_digit())));
+        let words: Vec<&str> = out.split(' ').filter(|w| !w.chars().all(|c| c.is_ascii_digit())).collect();
+        assert_eq!(words.join(" "), text.trim());
+    }
+
+    #[test]
+    fn test_symbol_variants() {
+        let out = run(Transform::SymbolVariants);
+        assert!(out.contains(" @ ") && out.contains(" % ") && out.contains(" & "));
+    }
+
+    #[test]
+    fn test_pipeline_order_and_determinism() {
+        let quote = Quote::from_text(Some("q".to_string()), TEXT, "en");
+        // Lowercasing runs after stripping whichever order they're given in
+        let a = apply(&[Transform::Lowercase, Transform::StripPunctuation], &quote);
+        let b = apply(&[Transform::StripPunctuation, Transform::Lowercase], &quote);
+        assert_eq!(a, b);
+        assert_eq!(a.text, "then at last she said its ten percent more and thats that");
+        assert_eq!(a.id, "q");
+
+        let all = apply(&Transform::ALL, &quote);
+        assert_eq!(all, apply(&Transform::ALL, &quote));
+        assert_eq!(apply(&[], &quote), quote);
+        assert_eq!(describe(&[Transform::InjectNumbers, Transform::Lowercase]), "Lowercase, Numbers");
+    }
+}
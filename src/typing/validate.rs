@@ -0,0 +1,273 @@
+// Checks for quote corpora, run as a test over the packs so a bad edit
+// to static/quotes.txt or static/packs fails the build instead of showing up in a typing session.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::quotes::parse_corpus;
+
+// Words per shingle for near-duplicate detection.
+const SHINGLE_WORDS: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct ValidationRules {
+    // Non-ASCII characters that may appear, e.g. accented letters for a German pack.
+    pub allowed_non_ascii: &'static str,
+    pub min_chars: usize,
+    pub max_chars: usize,
+    // Share of word shingles two quotes may have in common before they count as near-duplicates.
+    pub max_similarity: f64,
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        ValidationRules {
+            allowed_non_ascii: "",
+            min_chars: 50,
+            // Longer texts are fine, they are split into sections, see `sections`
+            max_chars: 20_000,
+            max_similarity: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IssueKind {
+    Malformed(String),
+    BlankLine,
+    NonAscii(char),
+    LeadingWhitespace,
+    TrailingWhitespace,
+    DoubleSpace,
+    TooShort(usize),
+    TooLong(usize),
+    Duplicate { of_line: usize },
+    NearDuplicate { of_line: usize, similarity: f64 },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            IssueKind::Malformed(msg) => write!(f, "malformed: {}", msg),
+            IssueKind::BlankLine => write!(f, "blank line"),
+            IssueKind::NonAscii(c) => write!(f, "non-ASCII character {:?} (U+{:04X})", c, *c as u32),
+            IssueKind::LeadingWhitespace => write!(f, "leading whitespace"),
+            IssueKind::TrailingWhitespace => write!(f, "trailing whitespace"),
+            IssueKind::DoubleSpace => write!(f, "double space"),
+            IssueKind::TooShort(n) => write!(f, "too short ({} characters)", n),
+            IssueKind::TooLong(n) => write!(f, "too long ({} characters)", n),
+            IssueKind::Duplicate { of_line } => write!(f, "duplicate of line {}", of_line),
+            IssueKind::NearDuplicate { of_line, similarity } => {
+                write!(f, "near-duplicate of line {} ({:.0}% similar)", of_line, similarity * 100.0)
+            }
+        }
+    }
+}
+
+pub fn validate_corpus(raw: &str, rules: &ValidationRules) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // (line number, quote text) for every line that parsed
+    let mut texts: Vec<(usize, String)> = Vec::new();
+
+    for (i, line) in raw.lines().enumerate() {
+        let line_number = i + 1;
+        let mut push = |kind| issues.push(Issue { line: line_number, kind });
+
+        if line.trim().is_empty() {
+            push(IssueKind::BlankLine);
+            continue;
+        }
+
+        // Parse lines one at a time so the reported line numbers match the file.
+        let text = match parse_corpus(line, "") {
+            Ok(mut quotes) if quotes.len() == 1 => quotes.remove(0).text,
+            Ok(_) => continue,
+            Err(e) => {
+                push(IssueKind::Malformed(e.message));
+                continue;
+            }
+        };
+        // Parsing trims the text, so the checks look at it as written
+        let written = written_text(line).unwrap_or_else(|| text.clone());
+
+        if written.starts_with(char::is_whitespace) {
+            push(IssueKind::LeadingWhitespace);
+        }
+        if line.ends_with(char::is_whitespace) || written.ends_with(char::is_whitespace) {
+            push(IssueKind::TrailingWhitespace);
+        }
+        if let Some(c) = written.chars().find(|c| !c.is_ascii() && !rules.allowed_non_ascii.contains(*c)) {
+            push(IssueKind::NonAscii(c));
+        }
+        // Padding is reported above, so only spaces between words count here
+        if written.trim().contains("  ") {
+            push(IssueKind::DoubleSpace);
+        }
+        let len = written.chars().count();
+        if len < rules.min_chars {
+            push(IssueKind::TooShort(len));
+        } else if len > rules.max_chars {
+            push(IssueKind::TooLong(len));
+        }
+        texts.push((line_number, text));
+    }
+
+    issues.extend(find_duplicates(&texts, rules.max_similarity));
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+// The whole line for plain text, the "text" field for a JSON line
+fn written_text(line: &str) -> Option<String> {
+    if !line.trim_start().starts_with('{') {
+        return Some(line.to_string());
+    }
+    let record: serde_json::Value = serde_json::from_str(line.trim()).ok()?;
+    record.get("text")?.as_str().map(str::to_string)
+}
+
+fn shingles(text: &str) -> HashSet<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.len() < SHINGLE_WORDS {
+        return std::iter::once(words.join(" ")).collect();
+    }
+    words.windows(SHINGLE_WORDS).map(|w| w.join(" ")).collect()
+}
+
+// Reports each later quote against the earliest one it resembles.
+fn find_duplicates(texts: &[(usize, String)], max_similarity: f64) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut exact: HashMap<&str, usize> = HashMap::new();
+    let shingle_sets: Vec<HashSet<String>> = texts.iter().map(|(_, t)| shingles(t)).collect();
+    // Which earlier quotes contain each shingle, so only overlapping pairs are compared.
+    let mut index: HashMap<&str, Vec<usize>> = HashMap::new();
+
+    for (i, (line, text)) in texts.iter().enumerate() {
+        if let Some(&of_line) = exact.get(text.as_str()) {
+            issues.push(Issue { line: *line, kind: IssueKind::Duplicate { of_line } });
+            continue;
+        }
+        exact.insert(text, *line);
+
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for shingle in &shingle_sets[i] {
+            for &j in index.get(shingle.as_str()).into_iter().flatten() {
+                *shared.entry(j).or_default() += 1;
+            }
+        }
+        let closest = shared
+            .into_iter()
+            .map(|(j, common)| {
+                let union = shingle_sets[i].len() + shingle_sets[j].len() - common;
+                (j, common as f64 / union as f64)
+            })
+            .filter(|&(_, similarity)| similarity > max_similarity)
+            .min_by_key(|&(j, _)| j);
+        if let Some((j, similarity)) = closest {
+            issues.push(Issue {
+                line: *line,
+                kind: IssueKind::NearDuplicate { of_line: texts[j].0, similarity },
+            });
+        }
+
+        for shingle in &shingle_sets[i] {
+            index.entry(shingle.as_str()).or_default().push(i);
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use super::super::quotes::PACK_SOURCES;
+
+    fn kinds(raw: &str) -> Vec<(usize, IssueKind)> {
+        let rules = ValidationRules { min_chars: 10, ..Default::default() };
+        validate_corpus(raw, &rules).into_iter().map(|i| (i.line, i.kind)).collect()
+    }
+
+    #[test]
+    fn test_packs_are_valid() {
+        for source in PACK_SOURCES.iter() {
+            let rules = ValidationRules { allowed_non_ascii: source.allowed_non_ascii, ..Default::default() };
+            let raw = source.read(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+            let issues = validate_corpus(&raw, &rules);
+            let report: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+            assert!(issues.is_empty(), "pack '{}':\n{}", source.id, report.join("\n"));
+        }
+    }
+
+    #[test]
+    fn test_formatting_issues() {
+        let raw = "A perfectly fine line.\nTrailing space here. \n\nTwo  spaces here.\nShort\n";
+        assert_eq!(
+            kinds(raw),
+            vec![
+                (2, IssueKind::TrailingWhitespace),
+                (3, IssueKind::BlankLine),
+                (4, IssueKind::DoubleSpace),
+                (5, IssueKind::TooShort(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_unless_allowed() {
+        let raw = "Caf\u{e9} au lait, s'il vous pla\u{ee}t.\nIt\u{2019}s a smart quote.";
+        assert_eq!(
+            kinds(raw),
+            vec![(1, IssueKind::NonAscii('\u{e9}')), (2, IssueKind::NonAscii('\u{2019}'))]
+        );
+        let rules = ValidationRules { allowed_non_ascii: "\u{e9}\u{ee}", min_chars: 10, ..Default::default() };
+        assert_eq!(validate_corpus(raw, &rules).len(), 1);
+    }
+
+    #[test]
+    fn test_structured_lines_are_checked() {
+        let raw = "{\"text\": \"Two  spaces in a JSON line.\"}\n{\"author\": \"missing text\"}";
+        let found = kinds(raw);
+        assert_eq!(found[0], (1, IssueKind::DoubleSpace));
+        assert!(matches!(found[1], (2, IssueKind::Malformed(_))));
+    }
+
+    #[test]
+    fn test_padded_text_is_reported() {
+        let raw = "{\"text\": \" Padding inside the JSON text. \"}\n   Indented plain text line.\n{\"text\": \"Tab at the end of it.\\t\"}";
+        assert_eq!(
+            kinds(raw),
+            vec![
+                (1, IssueKind::LeadingWhitespace),
+                (1, IssueKind::TrailingWhitespace),
+                (2, IssueKind::LeadingWhitespace),
+                (3, IssueKind::TrailingWhitespace),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let raw = "\
+The quick brown fox jumps over the lazy dog near the river bank.
+Something entirely different is written on this particular line.
+The quick brown fox jumps over the lazy dog near the river bank.
+The quick brown fox jumps over the lazy dog near the river shore.";
+        let found = kinds(raw);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], (3, IssueKind::Duplicate { of_line: 1 }));
+        assert!(matches!(found[1], (4, IssueKind::NearDuplicate { of_line: 1, .. })));
+    }
+}
@@ -0,0 +1,405 @@
+// Random text from frequency-ranked English words, for the word-count and timed modes.
+//
+// static/words/english.txt lists the 10,000 most frequent words, most frequent first,
+// so the smaller lists are prefixes of it. Generation is driven by a seeded PRNG and the
+// seed is part of the quote id, so a link to a generated test reproduces it exactly.
+// Like the quote packs, the list is read by the server and never shipped in the wasm:
+// the client picks a seed and asks the server for the test by id.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::drill::Drill;
+use super::quotes::{Pack, Quote};
+
+const ID_PREFIX: &str = "words";
+
+// Relative to the directory the server runs in
+pub const WORDS_PATH: &str = "static/words/english.txt";
+
+// Chance that a word is followed by punctuation, or replaced by a number.
+const PUNCTUATION_CHANCE: f64 = 0.2;
+const NUMBER_CHANCE: f64 = 0.1;
+
+// Enough words that nobody runs out before the time is up.
+const TIMED_MAX_WPM: u32 = 200;
+
+const SENTENCE_ENDS: [&str; 3] = [".", "?", "!"];
+const PAUSES: [&str; 3] = [",", ";", ":"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordList {
+    #[default]
+    Top200,
+    Top1k,
+    Top10k,
+}
+
+impl WordList {
+    pub const ALL: [WordList; 3] = [WordList::Top200, WordList::Top1k, WordList::Top10k];
+
+    pub fn size(&self) -> usize {
+        match self {
+            WordList::Top200 => 200,
+            WordList::Top1k => 1_000,
+            WordList::Top10k => 10_000,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WordList::Top200 => "English 200",
+            WordList::Top1k => "English 1k",
+            WordList::Top10k => "English 10k",
+        }
+    }
+
+    // Short form used in ids
+    fn code(&self) -> &'static str {
+        match self {
+            WordList::Top200 => "200",
+            WordList::Top1k => "1k",
+            WordList::Top10k => "10k",
+        }
+    }
+}
+
+// The ranked word list, one word per line
+pub struct WordBank {
+    words: Vec<String>,
+}
+
+impl WordBank {
+    pub fn parse(raw: &str) -> Self {
+        WordBank {
+            words: raw.lines().map(str::trim).filter(|w| !w.is_empty()).map(str::to_string).collect(),
+        }
+    }
+
+    pub fn read(root: &Path) -> io::Result<Self> {
+        fs::read_to_string(root.join(WORDS_PATH)).map(|raw| WordBank::parse(&raw))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn words(&self, list: WordList) -> &[String] {
+        &self.words[..list.size().min(self.words.len())]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WordOptions {
+    pub list: WordList,
+    pub punctuation: bool,
+    pub numbers: bool,
+}
+
+// What to practise. Quotes come from the selected pack, the other modes generate words.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    Quote,
+    Words(usize),
+    Timed(u32),
+}
+
+impl Mode {
+    pub const ALL: [Mode; 9] = [
+        Mode::Quote,
+        Mode::Words(10),
+        Mode::Words(25),
+        Mode::Words(50),
+        Mode::Words(100),
+        Mode::Timed(15),
+        Mode::Timed(30),
+        Mode::Timed(60),
+        Mode::Timed(120),
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            Mode::Quote => "Quote".to_string(),
+            Mode::Words(count) => format!("{} words", count),
+            Mode::Timed(seconds) => format!("{} seconds", seconds),
+        }
+    }
+
+    // Number of words to generate, or None for quotes
+    pub fn word_count(&self) -> Option<usize> {
+        match *self {
+            Mode::Quote => None,
+            Mode::Words(count) => Some(count),
+            Mode::Timed(seconds) => Some((seconds * TIMED_MAX_WPM / 60) as usize),
+        }
+    }
+
+    pub fn time_limit_ms(&self) -> Option<f64> {
+        match *self {
+            Mode::Timed(seconds) => Some(seconds as f64 * 1000.0),
+            _ => None,
+        }
+    }
+}
+
+// SplitMix64. Small, fast, and gives the same sequence on every platform.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    // Index chosen with probability proportional to its weight
+    pub fn pick_weighted(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().sum();
+        let mut target = self.next_f64() * total;
+        for (i, &w) in weights.iter().enumerate() {
+            if target < w {
+                return i;
+            }
+            target -= w;
+        }
+        weights.len() - 1
+    }
+}
+
+// Everything needed to regenerate a test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spec {
+    pub options: WordOptions,
+    pub count: usize,
+    pub seed: u64,
+}
+
+impl Spec {
+    // e.g. "words-1k-50-12345-p-n"
+    pub fn id(&self) -> String {
+        let mut id = format!("{}-{}-{}-{}", ID_PREFIX, self.options.list.code(), self.count, self.seed);
+        if self.options.punctuation {
+            id.push_str("-p");
+        }
+        if self.options.numbers {
+            id.push_str("-n");
+        }
+        id
+    }
+
+    pub fn from_id(id: &str) -> Option<Spec> {
+        let mut parts = id.split('-');
+        if parts.next()? != ID_PREFIX {
+            return None;
+        }
+        let code = parts.next()?;
+        let list = WordList::ALL.into_iter().find(|l| l.code() == code)?;
+        let count = parts.next()?.parse().ok().filter(|&c| c > 0)?;
+        let seed = parts.next()?.parse().ok()?;
+        let mut options = WordOptions { list, ..Default::default() };
+        for flag in parts {
+            match flag {
+                "p" => options.punctuation = true,
+                "n" => options.numbers = true,
+                _ => return None,
+            }
+        }
+        Some(Spec { options, count, seed })
+    }
+
+    pub fn generate(&self, bank: &WordBank) -> String {
+        let mut rng = Rng::new(self.seed);
+        let words = bank.words(self.options.list);
+        let mut out: Vec<String> = Vec::with_capacity(self.count);
+        let mut sentence_start = true;
+
+        for i in 0..self.count {
+            let mut word = if self.options.numbers && rng.chance(NUMBER_CHANCE) {
+                random_number(&mut rng)
+            } else {
+                // Avoid the same word twice in a row
+                let mut word = rng.pick(words);
+                while words.len() > 1 && out.last().is_some_and(|last| same_word(last, word)) {
+                    word = rng.pick(words);
+                }
+                word.clone()
+            };
+
+            if self.options.punctuation {
+                if sentence_start {
+                    word = capitalise(&word);
+                }
+                sentence_start = false;
+                let last = i + 1 == self.count;
+                if last {
+                    word.push('.');
+                } else if rng.chance(PUNCTUATION_CHANCE) {
+                    if rng.chance(0.5) {
+                        word.push_str(rng.pick::<&str>(&SENTENCE_ENDS));
+                        sentence_start = true;
+                    } else {
+                        word.push_str(rng.pick::<&str>(&PAUSES));
+                    }
+                }
+            }
+            out.push(word);
+        }
+        out.join(" ")
+    }
+
+    pub fn to_quote(&self, bank: &WordBank) -> Quote {
+        let mut quote = Quote::from_text(Some(self.id()), &self.generate(bank), "en");
+        quote.source = Some(self.options.list.label().to_string());
+        quote
+    }
+}
+
+// Ignores the capital and punctuation added to the previous word
+fn same_word(previous: &str, word: &str) -> bool {
+    previous.trim_end_matches(|c: char| c.is_ascii_punctuation()).eq_ignore_ascii_case(word)
+}
+
+// Word tests and drills are recreated from their id rather than stored anywhere. Drills
+// are made from the words of `pack`.
+pub fn generated_quote(id: &str, pack: &Pack, bank: &WordBank) -> Option<Quote> {
+    Spec::from_id(id)
+        .map(|spec| spec.to_quote(bank))
+        .or_else(|| Drill::from_id(id).map(|drill| drill.to_quote(pack, bank)))
+}
+
+// One to four digits
+pub fn random_number(rng: &mut Rng) -> String {
+    let digits = 1 + rng.below(4) as u32;
+    rng.below(10usize.pow(digits)).to_string()
+}
+
+pub fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bank() -> WordBank {
+        WordBank::read(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    fn spec(punctuation: bool, numbers: bool, seed: u64) -> Spec {
+        Spec {
+            options: WordOptions { list: WordList::Top1k, punctuation, numbers },
+            count: 50,
+            seed,
+        }
+    }
+
+    #[test]
+    fn test_lists_are_ranked_prefixes() {
+        let bank = bank();
+        let all = bank.words(WordList::Top10k);
+        assert_eq!(all.len(), 10_000);
+        assert_eq!(bank.words(WordList::Top200)[0], "the");
+        assert_eq!(bank.words(WordList::Top1k)[..200], *bank.words(WordList::Top200));
+        let mut unique: Vec<&String> = all.iter().collect();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), all.len(), "word list has duplicates");
+        assert!(all.iter().all(|w| w.chars().all(|c| c.is_ascii_lowercase())));
+        assert_eq!(WordBank::parse("a\n\n  b \n").words(WordList::Top200), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+        let xs: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(xs, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(xs, (0..5).map(|_| c.next_u64()).collect::<Vec<_>>());
+        assert!((0..1000).all(|_| a.below(7) < 7));
+        let picks: Vec<usize> = (0..1000).map(|_| a.pick_weighted(&[0.0, 1.0, 3.0])).collect();
+        assert!(!picks.contains(&0));
+        assert!(picks.iter().filter(|&&i| i == 2).count() > 600);
+    }
+
+    #[test]
+    fn test_seed_reproduces_text() {
+        let bank = bank();
+        assert_eq!(spec(true, true, 7).generate(&bank), spec(true, true, 7).generate(&bank));
+        assert_ne!(spec(true, true, 7).generate(&bank), spec(true, true, 8).generate(&bank));
+    }
+
+    #[test]
+    fn test_plain_words() {
+        let bank = bank();
+        let text = spec(false, false, 1).generate(&bank);
+        let words: Vec<&str> = text.split(' ').collect();
+        assert_eq!(words.len(), 50);
+        assert!(words.iter().all(|w| bank.words(WordList::Top1k).iter().any(|known| known == w)));
+    }
+
+    #[test]
+    fn test_punctuation_and_numbers() {
+        let text = spec(true, true, 3).generate(&bank());
+        assert_eq!(text.split(' ').count(), 50);
+        assert!(text.chars().next().unwrap().is_uppercase());
+        assert!(text.ends_with('.'));
+        assert!(text.chars().any(|c| c.is_ascii_digit()));
+        assert!(text.chars().any(|c| ",;:?!".contains(c)));
+    }
+
+    #[test]
+    fn test_id_round_trip() {
+        for s in [spec(false, false, 0), spec(true, false, 99), spec(true, true, u64::MAX)] {
+            assert_eq!(Spec::from_id(&s.id()), Some(s));
+        }
+        assert_eq!(spec(true, true, 5).id(), "words-1k-50-5-p-n");
+        assert_eq!(Spec::from_id("words-1k-0-5"), None);
+        assert_eq!(Spec::from_id("words-2k-50-5"), None);
+        assert_eq!(Spec::from_id("words-1k-50-5-x"), None);
+        assert_eq!(Spec::from_id("3fa2b9c01d4e"), None);
+    }
+
+    #[test]
+    fn test_generated_quote_is_usable() {
+        let quote = spec(false, false, 11).to_quote(&bank());
+        assert_eq!(quote.id, "words-1k-50-11");
+        assert_eq!(quote.source.as_deref(), Some("English 1k"));
+        assert_eq!(quote.text.split_whitespace().count(), 50);
+    }
+}
@@ -8,6 +8,22 @@ module.exports = {
         display: ["Major Mono Display", "sans-serif"],
         body: ["Raleway", "sans-serif"],
       },
+      // Caret blink, delayed inline so it only starts once typing pauses, and the flash
+      // on mistyped keys of the on-screen keyboard
+      keyframes: {
+        blink: {
+          "0%, 100%": { opacity: "1" },
+          "50%": { opacity: "0" },
+        },
+        flash: {
+          "0%": { opacity: "1" },
+          "100%": { opacity: "0" },
+        },
+      },
+      animation: {
+        blink: "blink 1s step-end infinite",
+        flash: "flash 400ms ease-out forwards",
+      },
     },
   },
   plugins: [],